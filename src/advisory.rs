@@ -0,0 +1,254 @@
+//! Security advisories and the versions they affect
+
+use cvss::Cvss;
+use error::{Error, Result};
+use platform::Target;
+use semver::Version;
+use toml;
+use version::{Range, VersionReq};
+
+/// An individual security advisory pertaining to a single vulnerability
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    /// Security advisory ID (e.g. RUSTSEC-YYYY-NNNN)
+    pub id: String,
+
+    /// Other IDs the vulnerability is known by (e.g. CVE-YYYY-NNNN or GHSA-xxxx-xxxx-xxxx)
+    pub aliases: Vec<String>,
+
+    /// Name of affected crate
+    pub package: String,
+
+    /// Whether this is a vulnerability, or informational (e.g. an unmaintained crate)
+    pub kind: Kind,
+
+    /// Versions which are patched and not vulnerable
+    pub patched_versions: Vec<VersionReq>,
+
+    /// Versions which were never vulnerable (e.g. those released before the bug was introduced)
+    pub unaffected_versions: Vec<VersionReq>,
+
+    /// Architectures the vulnerability is limited to (all, if empty)
+    pub affected_arch: Vec<String>,
+
+    /// Operating systems the vulnerability is limited to (all, if empty)
+    pub affected_os: Vec<String>,
+
+    /// CVSS v3 vector describing the severity of the vulnerability (optional)
+    pub cvss: Option<Cvss>,
+
+    /// Paths of the functions containing the vulnerability (e.g. `foo::parse_unchecked`), if
+    /// it's limited to particular functions
+    pub affected_functions: Vec<String>,
+
+    /// Date vulnerability was originally disclosed (optional)
+    pub date: Option<String>,
+
+    /// Date the advisory was withdrawn, if it was published in error (optional)
+    pub withdrawn: Option<String>,
+
+    /// URL with an announcement (e.g. blog post, PR, disclosure issue, CVE)
+    pub url: Option<String>,
+
+    /// One-liner description of a vulnerability
+    pub title: String,
+
+    /// Extended description of a vulnerability
+    pub description: String,
+}
+
+/// Kinds of advisory. Anything other than a vulnerability is informational, and given in an
+/// advisory's `informational` field.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    /// An exploitable security vulnerability
+    Vulnerability,
+
+    /// The crate is no longer maintained
+    Unmaintained,
+
+    /// The crate's API allows undefined behavior from safe code
+    Unsound,
+
+    /// Anything else worth knowing about the crate
+    Notice,
+}
+
+impl Kind {
+    /// All kinds of advisory, in the order they're reported
+    pub fn all() -> &'static [Kind] {
+        &[
+            Kind::Vulnerability,
+            Kind::Unmaintained,
+            Kind::Unsound,
+            Kind::Notice,
+        ]
+    }
+
+    /// Parse an informational kind, e.g. `unmaintained`
+    pub fn parse(name: &str) -> Option<Kind> {
+        Kind::all()
+            .iter()
+            .cloned()
+            .find(|kind| *kind != Kind::Vulnerability && kind.as_str() == name)
+    }
+
+    /// Name of the kind, as used in advisories and output
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Kind::Vulnerability => "vulnerability",
+            Kind::Unmaintained => "unmaintained",
+            Kind::Unsound => "unsound",
+            Kind::Notice => "notice",
+        }
+    }
+
+    /// Description of a number of findings of this kind, e.g. `2 unmaintained crates`
+    pub fn count(&self, count: usize) -> String {
+        let (singular, plural) = match *self {
+            Kind::Vulnerability => ("vulnerability", "vulnerabilities"),
+            Kind::Unmaintained => ("unmaintained crate", "unmaintained crates"),
+            Kind::Unsound => ("unsound crate", "unsound crates"),
+            Kind::Notice => ("notice", "notices"),
+        };
+
+        format!("{} {}", count, if count == 1 { singular } else { plural })
+    }
+}
+
+impl Advisory {
+    /// Parse an advisory from a TOML table
+    pub fn from_toml_table(table: &toml::value::Table) -> Result<Self> {
+        Ok(Advisory {
+            id: mandatory_string(table, "id")?,
+            aliases: optional_strings(table, "aliases")?,
+            package: mandatory_string(table, "package")?,
+            // Kinds added to the database after this release are still reported, as notices
+            kind: match optional_string(table, "informational")? {
+                Some(name) => Kind::parse(&name).unwrap_or(Kind::Notice),
+                None => Kind::Vulnerability,
+            },
+            patched_versions: match table.get("patched_versions") {
+                Some(value) => version_reqs(value, "patched_versions")?,
+                None => return Err(missing("patched_versions")),
+            },
+            unaffected_versions: match table.get("unaffected_versions") {
+                Some(value) => version_reqs(value, "unaffected_versions")?,
+                None => vec![],
+            },
+            affected_arch: optional_strings(table, "affected_arch")?,
+            affected_os: optional_strings(table, "affected_os")?,
+            cvss: match optional_string(table, "cvss")? {
+                Some(vector) => Some(Cvss::parse(&vector).map_err(|e| {
+                    Error::Parse(format!("invalid CVSS vector `{}`: {}", vector, e))
+                })?),
+                None => None,
+            },
+            affected_functions: optional_strings(table, "affected_functions")?,
+            date: optional_string(table, "date")?,
+            withdrawn: optional_string(table, "withdrawn")?,
+            url: optional_string(table, "url")?,
+            title: mandatory_string(table, "title")?,
+            description: mandatory_string(table, "description")?,
+        })
+    }
+
+    /// The first alias with the given prefix, e.g. the CVE ID for `CVE-`
+    pub fn alias(&self, prefix: &str) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.starts_with(prefix))
+            .map(|alias| &alias[..])
+    }
+
+    /// Is the given version of the crate affected by this advisory?
+    pub fn is_affected(&self, version: &Version) -> bool {
+        !self
+            .patched_versions
+            .iter()
+            .chain(&self.unaffected_versions)
+            .any(|req| req.matches(version))
+    }
+
+    /// Can this advisory apply to a build for the given target?
+    pub fn applies_to(&self, target: &Target) -> bool {
+        (self.affected_arch.is_empty() || self.affected_arch.contains(&target.arch))
+            && (self.affected_os.is_empty() || self.affected_os.contains(&target.os))
+    }
+
+    /// Human-readable description of the platforms this advisory is limited to (e.g. `windows`
+    /// or `any OS on x86`), if any
+    pub fn platforms(&self) -> Option<String> {
+        match (self.affected_os.is_empty(), self.affected_arch.is_empty()) {
+            (true, true) => None,
+            (false, true) => Some(self.affected_os.join(", ")),
+            (true, false) => Some(format!("any OS on {}", self.affected_arch.join(", "))),
+            (false, false) => Some(format!(
+                "{} on {}",
+                self.affected_os.join(", "),
+                self.affected_arch.join(", ")
+            )),
+        }
+    }
+
+    /// Ranges of versions affected by this advisory: those which are neither patched nor
+    /// unaffected
+    pub fn affected_ranges(&self) -> Vec<Range> {
+        let ranges: Vec<_> = self
+            .patched_versions
+            .iter()
+            .chain(&self.unaffected_versions)
+            .map(|req| req.range())
+            .collect();
+
+        Range::complement(&ranges)
+    }
+}
+
+fn optional_string(table: &toml::value::Table, key: &str) -> Result<Option<String>> {
+    match table.get(key) {
+        Some(value) => match value.as_str() {
+            Some(s) => Ok(Some(s.to_owned())),
+            None => Err(Error::Parse(format!("`{}` must be a string", key))),
+        },
+        None => Ok(None),
+    }
+}
+
+fn mandatory_string(table: &toml::value::Table, key: &str) -> Result<String> {
+    optional_string(table, key)?.ok_or_else(|| missing(key))
+}
+
+fn optional_strings(table: &toml::value::Table, key: &str) -> Result<Vec<String>> {
+    let invalid = || Error::Parse(format!("`{}` must be an array of strings", key));
+
+    match table.get(key) {
+        Some(value) => value
+            .as_array()
+            .ok_or_else(&invalid)?
+            .iter()
+            .map(|s| s.as_str().map(|s| s.to_owned()).ok_or_else(&invalid))
+            .collect(),
+        None => Ok(vec![]),
+    }
+}
+
+fn version_reqs(value: &toml::Value, key: &str) -> Result<Vec<VersionReq>> {
+    let invalid = || Error::Parse(format!("`{}` must be an array of strings", key));
+
+    value
+        .as_array()
+        .ok_or_else(&invalid)?
+        .iter()
+        .map(|req| {
+            let req = req.as_str().ok_or_else(&invalid)?;
+
+            VersionReq::parse(req)
+                .map_err(|e| Error::Parse(format!("invalid version requirement `{}`: {}", req, e)))
+        })
+        .collect()
+}
+
+fn missing(key: &str) -> Error {
+    Error::Parse(format!("missing `{}`", key))
+}
@@ -0,0 +1,238 @@
+//! Output for the `cargo audit db` subcommands, which look up advisories without auditing a
+//! project
+
+use advisory::{Advisory, Kind};
+use database::AdvisoryDatabase;
+use lint::{Report, Severity};
+use output::{affected_versions, attribute, cvss_json, severity, OutputFormat};
+use serde_json;
+use shell::Shell;
+use term;
+use term::color::{BLACK, GREEN, RED, YELLOW};
+use version::VersionReq;
+
+/// Print a one-line summary of each of the given advisories
+pub fn list(
+    shell: &mut Shell,
+    db: &AdvisoryDatabase,
+    advisories: &[&Advisory],
+    output_format: &OutputFormat,
+) -> term::Result<()> {
+    match *output_format {
+        OutputFormat::Text => {
+            for advisory in advisories {
+                let date = match advisory.date {
+                    Some(ref date) => format!(" ({})", date),
+                    None => String::new(),
+                };
+
+                let color = match advisory.kind {
+                    Kind::Vulnerability => RED,
+                    _ => YELLOW,
+                };
+
+                shell.say_status(
+                    &advisory.id,
+                    format!("{}{}: {}", advisory.package, date, advisory.title),
+                    color,
+                    false,
+                )?;
+            }
+        }
+        OutputFormat::Json => {
+            let advisories: Vec<_> = advisories
+                .iter()
+                .map(|advisory| to_json(db, advisory))
+                .collect();
+            shell.say(json!(advisories), BLACK)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Print everything known about an advisory
+pub fn show(
+    shell: &mut Shell,
+    db: &AdvisoryDatabase,
+    advisory: &Advisory,
+    output_format: &OutputFormat,
+) -> term::Result<()> {
+    if let OutputFormat::Json = *output_format {
+        return shell.say(to_json(db, advisory), BLACK);
+    }
+
+    attribute(shell, "ID", &advisory.id)?;
+
+    if !advisory.aliases.is_empty() {
+        attribute(shell, "Aliases", &advisory.aliases.join(", "))?;
+    }
+    attribute(shell, "Crate", &advisory.package)?;
+    attribute(shell, "Kind", advisory.kind.as_str())?;
+
+    if let Some(ref date) = advisory.date {
+        attribute(shell, "Date", date)?;
+    }
+
+    if let Some(ref withdrawn) = advisory.withdrawn {
+        attribute(shell, "Withdrawn", withdrawn)?;
+    }
+
+    if let Some(ref url) = advisory.url {
+        attribute(shell, "URL", url)?;
+    }
+
+    attribute(shell, "Title", &advisory.title)?;
+
+    if let Some(ref cvss) = advisory.cvss {
+        attribute(shell, "Severity", &severity(cvss))?;
+    }
+
+    attribute(shell, "Source", db.source(advisory))?;
+
+    let patched: Vec<_> = advisory
+        .patched_versions
+        .iter()
+        .map(|req| req.to_string())
+        .collect();
+
+    if patched.is_empty() {
+        attribute(shell, "Patched versions", "none")?;
+    } else {
+        attribute(shell, "Patched versions", &patched.join(", "))?;
+    }
+
+    if !advisory.unaffected_versions.is_empty() {
+        let unaffected: Vec<_> = advisory
+            .unaffected_versions
+            .iter()
+            .map(|req| req.to_string())
+            .collect();
+
+        attribute(shell, "Unaffected versions", &unaffected.join(", "))?;
+    }
+
+    attribute(shell, "Affected versions", &affected_versions(advisory))?;
+
+    if let Some(platforms) = advisory.platforms() {
+        attribute(shell, "Platforms", &platforms)?;
+    }
+
+    if !advisory.affected_functions.is_empty() {
+        attribute(shell, "Functions", &advisory.affected_functions.join(", "))?;
+    }
+
+    shell.say(format!("\n{}", advisory.description.trim()), BLACK)
+}
+
+fn to_json(db: &AdvisoryDatabase, advisory: &Advisory) -> serde_json::Value {
+    let versions =
+        |reqs: &[VersionReq]| -> Vec<String> { reqs.iter().map(|req| req.to_string()).collect() };
+
+    let affected: Vec<_> = advisory
+        .affected_ranges()
+        .iter()
+        .map(|range| range.to_string())
+        .collect();
+
+    json!({
+        "id": advisory.id,
+        "cve": advisory.alias("CVE-"),
+        "ghsa": advisory.alias("GHSA-"),
+        "aliases": advisory.aliases,
+        "package": advisory.package,
+        "kind": advisory.kind.as_str(),
+        "cvss": advisory.cvss.as_ref().map(cvss_json),
+        "title": advisory.title,
+        "description": advisory.description,
+        "date": advisory.date,
+        "withdrawn": advisory.withdrawn,
+        "url": advisory.url,
+        "patched_versions": versions(&advisory.patched_versions),
+        "unaffected_versions": versions(&advisory.unaffected_versions),
+        "affected_versions": affected,
+        "affected_arch": advisory.affected_arch,
+        "affected_os": advisory.affected_os,
+        "affected_functions": advisory.affected_functions,
+        "source": db.source(advisory),
+    })
+}
+
+/// Print the problems found by `cargo audit db lint`
+pub fn lint_report(
+    shell: &mut Shell,
+    report: &Report,
+    output_format: &OutputFormat,
+) -> term::Result<()> {
+    if let OutputFormat::Json = *output_format {
+        let problems: Vec<_> = report
+            .problems
+            .iter()
+            .map(|problem| {
+                json!({
+                    "file": problem.path.display().to_string(),
+                    "line": problem.line,
+                    "severity": match problem.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    },
+                    "message": problem.message,
+                })
+            })
+            .collect();
+
+        return shell.say(json!(problems), BLACK);
+    }
+
+    for problem in &report.problems {
+        let location = match problem.line {
+            Some(line) => format!("{}:{}", problem.path.display(), line),
+            None => problem.path.display().to_string(),
+        };
+
+        let (status, color) = match problem.severity {
+            Severity::Error => ("error:", RED),
+            Severity::Warning => ("warning:", YELLOW),
+        };
+
+        shell.say_status(
+            status,
+            format!("{}: {}", location, problem.message),
+            color,
+            false,
+        )?;
+    }
+
+    let (errors, warnings) = (
+        report.count(Severity::Error),
+        report.count(Severity::Warning),
+    );
+
+    if errors == 0 && warnings == 0 {
+        return shell.say_status(
+            "Success",
+            format!(
+                "No problems found in {}",
+                plural(report.advisories, "advisory", "advisories")
+            ),
+            GREEN,
+            true,
+        );
+    }
+
+    shell.say_status(
+        if errors > 0 { "\nerror:" } else { "\nwarning:" },
+        format!(
+            "{} and {} found in {}",
+            plural(errors, "error", "errors"),
+            plural(warnings, "warning", "warnings"),
+            plural(report.advisories, "advisory", "advisories")
+        ),
+        if errors > 0 { RED } else { YELLOW },
+        false,
+    )
+}
+
+fn plural(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
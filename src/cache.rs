@@ -0,0 +1,270 @@
+//! On-disk cache of the most recently fetched advisory database
+
+use config;
+use error::{Error, Result};
+use files;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directory within the cache holding a git clone for each advisory database URL
+const REPOSITORIES_DIR: &'static str = "repositories";
+
+/// Directory within the cache listing, for each lockfile and advisory database, the advisories
+/// which were withdrawn as of the previous run
+const WITHDRAWN_DIR: &'static str = "withdrawn";
+
+/// Directory where the advisory database is cached between runs
+#[derive(Debug)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+/// Advisory database loaded from the cache
+#[derive(Debug)]
+pub struct CachedDatabase {
+    /// TOML serialization of the database
+    pub toml: String,
+
+    /// Time elapsed since the database was fetched
+    pub age: Duration,
+}
+
+impl Cache {
+    /// Use the given directory as the cache
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        Cache { dir: dir.into() }
+    }
+
+    /// Default cache location: `$CARGO_HOME/advisory-db` (i.e. `~/.cargo/advisory-db`)
+    pub fn default_dir() -> Option<PathBuf> {
+        config::cargo_home().map(|cargo_home| cargo_home.join("advisory-db"))
+    }
+
+    /// Path to the cached copy of the database fetched from the given URL
+    pub fn path(&self, url: &str) -> PathBuf {
+        let name = file_name(url);
+
+        self.dir
+            .join(format!("{}.toml", name.trim_end_matches(".toml")))
+    }
+
+    /// Location of the local clone of the advisory-db git repository at the given URL
+    pub fn repository_dir(&self, url: &str) -> PathBuf {
+        self.dir.join(REPOSITORIES_DIR).join(file_name(url))
+    }
+
+    /// Path to the cached detached signature of the database fetched from the given URL
+    pub fn signature_path(&self, url: &str) -> PathBuf {
+        self.path(url).with_extension("toml.asc")
+    }
+
+    /// Load the database cached for the given URL, if one has been stored previously
+    pub fn load(&self, url: &str) -> Result<Option<CachedDatabase>> {
+        let path = self.path(url);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let toml = files::read_to_string(&path)?;
+        let modified = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(|e| io_error(&path, e))?;
+
+        // Clocks may go backwards: treat a timestamp in the future as fresh
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_else(|_| Duration::from_secs(0));
+
+        Ok(Some(CachedDatabase {
+            toml: toml,
+            age: age,
+        }))
+    }
+
+    /// Load the cached detached signature for the given URL, if one has been stored previously
+    pub fn load_signature(&self, url: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.signature_path(url);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        files::read(&path).map(Some)
+    }
+
+    /// Replace the database cached for the given URL with a freshly fetched copy
+    pub fn store(&self, url: &str, toml: &str) -> Result<()> {
+        self.write(&self.path(url), toml.as_bytes())
+    }
+
+    /// Replace the cached detached signature for the given URL
+    pub fn store_signature(&self, url: &str, signature: &[u8]) -> Result<()> {
+        self.write(&self.signature_path(url), signature)
+    }
+
+    /// IDs of the advisories from the `source` database which had been withdrawn as of the
+    /// previous audit of `lockfile`, or `None` if it hasn't been audited against that database
+    pub fn load_withdrawn(&self, lockfile: &Path, source: &str) -> Result<Option<Vec<String>>> {
+        let path = self.withdrawn_path(lockfile, source);
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data = files::read_to_string(&path)?;
+        Ok(Some(data.lines().map(|id| id.to_owned()).collect()))
+    }
+
+    /// Record the IDs of the advisories from the `source` database which are currently withdrawn,
+    /// for comparison by the next audit of `lockfile`
+    pub fn store_withdrawn(&self, lockfile: &Path, source: &str, ids: &[&str]) -> Result<()> {
+        let mut data = String::new();
+
+        for id in ids {
+            data.push_str(id);
+            data.push('\n');
+        }
+
+        self.write(&self.withdrawn_path(lockfile, source), data.as_bytes())
+    }
+
+    /// Lockfile paths and database URLs can be too long for file names, so state is stored
+    /// under a digest of both
+    fn withdrawn_path(&self, lockfile: &Path, source: &str) -> PathBuf {
+        let key = format!("{}\n{}", lockfile.display(), source);
+        let digest: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        self.dir.join(WITHDRAWN_DIR).join(format!("{}.txt", digest))
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let dir = path.parent().unwrap_or(&self.dir);
+        fs::create_dir_all(dir).map_err(|e| io_error(dir, e))?;
+
+        // Write to a temporary file of this run's own first, so concurrent runs never observe
+        // (or rename into place) a partially written file
+        let tmp_path = files::write_new(dir, "tmp", data)?;
+
+        fs::rename(&tmp_path, path).map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            io_error(path, e)
+        })
+    }
+}
+
+/// Describe the age of a cached database in human-friendly terms
+pub fn describe_age(age: &Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+
+    match (days, hours, minutes) {
+        (0, 0, 0) => "less than a minute".to_owned(),
+        (0, 0, 1) => "1 minute".to_owned(),
+        (0, 0, m) => format!("{} minutes", m),
+        (0, 1, _) => "1 hour".to_owned(),
+        (0, h, _) => format!("{} hours", h),
+        (1, _, _) => "1 day".to_owned(),
+        (d, _, _) => format!("{} days", d),
+    }
+}
+
+/// Derive a filesystem-safe name from a URL, e.g.
+/// `raw.githubusercontent.com-RustSec-advisory-db-master-Advisories.toml`
+fn file_name(url: &str) -> String {
+    url.splitn(2, "://")
+        .last()
+        .unwrap_or(url)
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn io_error(path: &Path, err: io::Error) -> Error {
+    Error::IO(format!("{}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cache;
+    use files::ScratchDir;
+    use std::fs;
+    use std::path::Path;
+    use std::thread;
+
+    #[test]
+    fn repository_dir_per_url() {
+        let cache = Cache::new("/cache");
+        let a = cache.repository_dir("https://github.com/RustSec/advisory-db.git");
+        let b = cache.repository_dir("https://example.com/mirror/advisory-db.git");
+
+        assert_ne!(a, b);
+        assert_eq!(
+            a,
+            Path::new("/cache/repositories/github.com-RustSec-advisory-db.git")
+        );
+    }
+
+    #[test]
+    fn withdrawn_per_lockfile_and_source() {
+        let dir = ScratchDir::new("withdrawn");
+        let cache = Cache::new(dir.to_owned());
+        let (a, b) = (Path::new("/a/Cargo.lock"), Path::new("/b/Cargo.lock"));
+        let source = "https://example.com/advisory-db.git";
+
+        // Nothing has been recorded before the first run
+        assert_eq!(cache.load_withdrawn(a, source).unwrap(), None);
+
+        cache.store_withdrawn(a, source, &[]).unwrap();
+        assert_eq!(cache.load_withdrawn(a, source).unwrap(), Some(vec![]));
+
+        cache
+            .store_withdrawn(a, source, &["RUSTSEC-2017-0001"])
+            .unwrap();
+        assert_eq!(
+            cache.load_withdrawn(a, source).unwrap(),
+            Some(vec!["RUSTSEC-2017-0001".to_owned()])
+        );
+
+        assert_eq!(cache.load_withdrawn(b, source).unwrap(), None);
+        assert_eq!(cache.load_withdrawn(a, "local.toml").unwrap(), None);
+    }
+
+    #[test]
+    fn concurrent_stores() {
+        let dir = ScratchDir::new("store");
+        let url = "https://example.com/Advisories.toml";
+
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let dir = dir.to_owned();
+                thread::spawn(move || {
+                    let toml = format!("# {}\n", i).repeat(10000);
+                    Cache::new(dir).store(url, &toml).unwrap();
+                })
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // Whichever store finished last wins, but never with a mix of contents
+        let toml = Cache::new(dir.to_owned()).load(url).unwrap().unwrap().toml;
+        assert_eq!(toml, toml[..toml.find('\n').unwrap() + 1].repeat(10000));
+        assert_eq!(fs::read_dir(&*dir).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,165 @@
+//! Settings read from cargo's own configuration
+//!
+//! Options such as `http.proxy` are shared with cargo, so a network which needs them to be set
+//! for `cargo fetch` to work needs no extra configuration for `cargo audit` either.
+
+use error::{Error, Result};
+use files;
+use std::env;
+use std::path::{Path, PathBuf};
+use toml;
+
+/// Cargo configuration assembled from `.cargo/config` files and `CARGO_*` environment variables
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Parsed configuration files, most specific (i.e. closest to the working directory) first
+    files: Vec<ConfigFile>,
+
+    /// Errors from configuration files which couldn't be used, and so were ignored
+    ignored: Vec<Error>,
+}
+
+#[derive(Debug)]
+struct ConfigFile {
+    /// Directory containing the `.cargo` directory the file was found in
+    root: PathBuf,
+
+    /// Parsed contents of the file
+    value: toml::Value,
+}
+
+impl Config {
+    /// Load the configuration which applies to the current directory, in the same locations
+    /// cargo searches: `.cargo/config` in the current directory and each of its ancestors,
+    /// followed by `$CARGO_HOME/config`.
+    ///
+    /// Files which can't be read or parsed (e.g. because they use TOML syntax newer than this
+    /// parser understands) are ignored rather than preventing the audit.
+    pub fn load() -> Self {
+        let mut config = Config::default();
+
+        if let Ok(cwd) = env::current_dir() {
+            for dir in cwd.ancestors() {
+                config.add_file(dir, &dir.join(".cargo"));
+            }
+        }
+
+        if let Some(cargo_home) = cargo_home() {
+            let root = cargo_home.parent().unwrap_or(&cargo_home).to_owned();
+
+            // Skip `~/.cargo/config` if it was already found as an ancestor of the current dir
+            if !config.files.iter().any(|file| file.root == root) {
+                config.add_file(&root, &cargo_home);
+            }
+        }
+
+        config
+    }
+
+    /// Problems with the configuration files which were ignored
+    pub fn ignored(&self) -> &[Error] {
+        &self.ignored
+    }
+
+    /// Look up a string setting by its dotted key (e.g. `http.proxy`). Environment variables
+    /// (e.g. `CARGO_HTTP_PROXY`) take precedence over configuration files.
+    pub fn get_str(&self, key: &str) -> Option<String> {
+        if let Some(value) = env_var(key) {
+            return Some(value);
+        }
+
+        self.lookup(key)
+            .and_then(|(value, _)| value.as_str().map(|s| s.to_owned()))
+    }
+
+    /// Look up a non-negative integer setting by its dotted key (e.g. `http.timeout`)
+    pub fn get_u64(&self, key: &str) -> Result<Option<u64>> {
+        let invalid = || Error::Parse(format!("`{}` must be a non-negative integer", key));
+
+        if let Some(value) = env_var(key) {
+            return value.parse().map(Some).map_err(|_| invalid());
+        }
+
+        match self.lookup(key) {
+            Some((value, _)) => match value.as_integer() {
+                Some(n) if n >= 0 => Ok(Some(n as u64)),
+                _ => Err(invalid()),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Look up a path setting by its dotted key (e.g. `http.cainfo`). Relative paths in
+    /// configuration files are resolved against the directory containing `.cargo`.
+    pub fn get_path(&self, key: &str) -> Option<PathBuf> {
+        if let Some(value) = env_var(key) {
+            return Some(PathBuf::from(value));
+        }
+
+        self.lookup(key)
+            .and_then(|(value, root)| value.as_str().map(|path| root.join(path)))
+    }
+
+    /// Find the most specific value for a dotted key, along with the root of the file it was
+    /// set in
+    fn lookup(&self, key: &str) -> Option<(&toml::Value, &Path)> {
+        for file in &self.files {
+            let mut value = Some(&file.value);
+
+            for part in key.split('.') {
+                value = value.and_then(|v| v.get(part));
+            }
+
+            if let Some(value) = value {
+                return Some((value, &file.root));
+            }
+        }
+
+        None
+    }
+
+    /// Parse `config` (or `config.toml`) in `dir`, if present
+    fn add_file(&mut self, root: &Path, dir: &Path) {
+        let path = ["config", "config.toml"]
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file());
+
+        let path = match path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let value = files::read_to_string(&path).and_then(|data| {
+            data.parse::<toml::Value>()
+                .map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))
+        });
+
+        match value {
+            Ok(value) => self.files.push(ConfigFile {
+                root: root.to_owned(),
+                value: value,
+            }),
+            Err(e) => self.ignored.push(e),
+        }
+    }
+}
+
+/// Cargo's home directory: `$CARGO_HOME`, or `~/.cargo` if unset
+pub fn cargo_home() -> Option<PathBuf> {
+    match env::var_os("CARGO_HOME") {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => env::home_dir().map(|home| home.join(".cargo")),
+    }
+}
+
+/// Value of the environment variable overriding a dotted key, e.g. `CARGO_HTTP_PROXY` for
+/// `http.proxy`
+fn env_var(key: &str) -> Option<String> {
+    let name = format!(
+        "CARGO_{}",
+        key.replace('.', "_").replace('-', "_").to_uppercase()
+    );
+
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
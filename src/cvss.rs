@@ -0,0 +1,283 @@
+//! CVSS v3 vectors, which give the severity of a vulnerability
+//!
+//! Only the base metrics are scored. Temporal and environmental metrics are accepted, but
+//! depend on circumstances an advisory can't know about, so they're ignored.
+
+use std::fmt;
+
+/// Base metrics, all of which a vector must include
+const BASE_METRICS: &'static [&'static str] = &["AV", "AC", "PR", "UI", "S", "C", "I", "A"];
+
+/// Temporal and environmental metrics, which are allowed but ignored
+const OTHER_METRICS: &'static [&'static str] = &[
+    "E", "RL", "RC", "CR", "IR", "AR", "MAV", "MAC", "MPR", "MUI", "MS", "MC", "MI", "MA",
+];
+
+/// A parsed CVSS v3 vector such as `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cvss {
+    /// The vector as written
+    vector: String,
+
+    /// Base score in tenths, from 0 to 100 (scores only ever have one decimal place)
+    score: u8,
+}
+
+/// Qualitative severity rating of a CVSS score
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Score of 0.0
+    None,
+
+    /// Score of 0.1 to 3.9
+    Low,
+
+    /// Score of 4.0 to 6.9
+    Medium,
+
+    /// Score of 7.0 to 8.9
+    High,
+
+    /// Score of 9.0 to 10.0
+    Critical,
+}
+
+impl Cvss {
+    /// Parse a CVSS v3.0 or v3.1 vector and calculate its base score
+    pub fn parse(vector: &str) -> Result<Self, String> {
+        let mut parts = vector.split('/');
+
+        match parts.next() {
+            Some("CVSS:3.0") | Some("CVSS:3.1") => (),
+            _ => return Err("expected a `CVSS:3.0` or `CVSS:3.1` prefix".to_owned()),
+        }
+
+        let mut metrics = vec![];
+
+        for part in parts {
+            let mut fields = part.splitn(2, ':');
+            let (name, value) = match (fields.next(), fields.next()) {
+                (Some(name), Some(value)) if !value.is_empty() => (name, value),
+                _ => return Err(format!("malformed metric `{}`", part)),
+            };
+
+            if !BASE_METRICS.contains(&name) && !OTHER_METRICS.contains(&name) {
+                return Err(format!("unknown metric `{}`", name));
+            }
+
+            if metrics.iter().any(|&(n, _)| n == name) {
+                return Err(format!("duplicate metric `{}`", name));
+            }
+
+            metrics.push((name, value));
+        }
+
+        let metric = |name: &str| -> Result<&str, String> {
+            metrics
+                .iter()
+                .find(|&&(n, _)| n == name)
+                .map(|&(_, value)| value)
+                .ok_or_else(|| format!("missing metric `{}`", name))
+        };
+
+        let invalid = |name: &str, value: &str| format!("invalid value `{}` for `{}`", value, name);
+
+        let scope_changed = match metric("S")? {
+            "U" => false,
+            "C" => true,
+            other => return Err(invalid("S", other)),
+        };
+
+        let attack_vector = match metric("AV")? {
+            "N" => 0.85,
+            "A" => 0.62,
+            "L" => 0.55,
+            "P" => 0.2,
+            other => return Err(invalid("AV", other)),
+        };
+
+        let attack_complexity = match metric("AC")? {
+            "L" => 0.77,
+            "H" => 0.44,
+            other => return Err(invalid("AC", other)),
+        };
+
+        let privileges_required = match (metric("PR")?, scope_changed) {
+            ("N", _) => 0.85,
+            ("L", false) => 0.62,
+            ("L", true) => 0.68,
+            ("H", false) => 0.27,
+            ("H", true) => 0.5,
+            (other, _) => return Err(invalid("PR", other)),
+        };
+
+        let user_interaction = match metric("UI")? {
+            "N" => 0.85,
+            "R" => 0.62,
+            other => return Err(invalid("UI", other)),
+        };
+
+        let mut impacts = vec![];
+
+        for name in &["C", "I", "A"] {
+            impacts.push(match metric(name)? {
+                "H" => 0.56,
+                "L" => 0.22,
+                "N" => 0.0,
+                other => return Err(invalid(name, other)),
+            });
+        }
+
+        let iss = 1.0 - impacts.iter().fold(1.0, |acc, impact| acc * (1.0 - impact));
+
+        let impact = if scope_changed {
+            7.52 * (iss - 0.029) - 3.25 * (iss - 0.02f64).powi(15)
+        } else {
+            6.42 * iss
+        };
+
+        let exploitability =
+            8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+
+        let score = if impact <= 0.0 {
+            0
+        } else if scope_changed {
+            round_up((1.08 * (impact + exploitability)).min(10.0))
+        } else {
+            round_up((impact + exploitability).min(10.0))
+        };
+
+        Ok(Cvss {
+            vector: vector.to_owned(),
+            score: score,
+        })
+    }
+
+    /// The vector as written
+    pub fn vector(&self) -> &str {
+        &self.vector
+    }
+
+    /// Base score, from 0.0 to 10.0
+    pub fn score(&self) -> f64 {
+        f64::from(self.score) / 10.0
+    }
+
+    /// Severity rating of the base score
+    pub fn severity(&self) -> Severity {
+        match self.score {
+            0 => Severity::None,
+            score if score < 40 => Severity::Low,
+            score if score < 70 => Severity::Medium,
+            score if score < 90 => Severity::High,
+            _ => Severity::Critical,
+        }
+    }
+}
+
+impl Severity {
+    /// Parse a severity rating, e.g. `high`
+    pub fn parse(name: &str) -> Option<Severity> {
+        match name {
+            "none" => Some(Severity::None),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+
+    /// Name of the rating, e.g. `high`
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Severity::None => "none",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.as_str())
+    }
+}
+
+/// Round up to one decimal place, giving the result in tenths. This is done as specified by
+/// CVSS v3.1, which avoids the floating point inaccuracies of simply rounding up `x * 10`.
+fn round_up(x: f64) -> u8 {
+    let int_input = (x * 100_000.0).round() as u64;
+
+    if int_input % 10_000 == 0 {
+        (int_input / 10_000) as u8
+    } else {
+        (int_input / 10_000 + 1) as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cvss, Severity};
+
+    fn score(vector: &str) -> f64 {
+        Cvss::parse(vector).unwrap().score()
+    }
+
+    #[test]
+    fn base_scores() {
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), 9.8);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H"), 10.0);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"), 6.1);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H"), 8.8);
+        assert_eq!(score("CVSS:3.0/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"), 7.5);
+        assert_eq!(score("CVSS:3.1/AV:L/AC:H/PR:H/UI:R/S:U/C:L/I:N/A:N"), 1.8);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N"), 0.0);
+    }
+
+    #[test]
+    fn temporal_metrics_are_ignored() {
+        assert_eq!(
+            score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H/E:P/RL:O/RC:C"),
+            9.8
+        );
+    }
+
+    #[test]
+    fn severities() {
+        let severity = |vector| Cvss::parse(vector).unwrap().severity();
+
+        assert_eq!(
+            severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"),
+            Severity::Critical
+        );
+        assert_eq!(
+            severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"),
+            Severity::High
+        );
+        assert_eq!(
+            severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"),
+            Severity::Medium
+        );
+        assert_eq!(
+            severity("CVSS:3.1/AV:L/AC:H/PR:H/UI:R/S:U/C:L/I:N/A:N"),
+            Severity::Low
+        );
+        assert_eq!(
+            severity("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N"),
+            Severity::None
+        );
+    }
+
+    #[test]
+    fn invalid_vectors() {
+        assert!(Cvss::parse("AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(Cvss::parse("CVSS:2.0/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(Cvss::parse("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H").is_err());
+        assert!(Cvss::parse("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H/A:L").is_err());
+        assert!(Cvss::parse("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H").is_err());
+        assert!(Cvss::parse("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H/XX:Y").is_err());
+    }
+}
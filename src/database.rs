@@ -0,0 +1,319 @@
+//! Advisory database assembled from one or more TOML documents
+
+use advisory::Advisory;
+use date;
+use error::{Error, Result};
+use files;
+use lockfile::{Lockfile, Package};
+use semver::Version;
+use std::collections::btree_map;
+use std::collections::btree_map::Entry::{Occupied, Vacant};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use toml;
+
+/// Prefix required for the IDs of project-specific advisories, so they can't collide with
+/// the `RUSTSEC-YYYY-NNNN` IDs of published ones
+pub const LOCAL_ID_PREFIX: &'static str = "LOCAL-";
+
+/// A collection of security advisories, indexed both by ID and crate
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryDatabase {
+    advisories: BTreeMap<String, Advisory>,
+    crates: HashMap<String, Vec<String>>,
+    sources: HashMap<String, String>,
+
+    /// Commits checked out from git databases, by source
+    commits: HashMap<String, String>,
+}
+
+/// A vulnerable package and the associated advisory
+#[derive(Debug)]
+pub struct Vulnerability<'a> {
+    /// A security advisory for which the package is vulnerable
+    pub advisory: &'a Advisory,
+
+    /// A vulnerable package
+    pub package: &'a Package,
+
+    /// Name of the database the advisory was obtained from
+    pub source: &'a str,
+
+    /// Commit of the database the advisory was obtained from, if it's a git repository
+    pub commit: Option<&'a str>,
+}
+
+impl AdvisoryDatabase {
+    /// Parse the advisory database from a TOML serialization of it. Advisories are attributed
+    /// to the given `source` (e.g. the URL the data was fetched from).
+    pub fn from_toml(data: &str, source: &str) -> Result<Self> {
+        let mut db = Self::default();
+        db.add_toml(data, source)?;
+        Ok(db)
+    }
+
+    /// Load the advisory database from a local path.
+    ///
+    /// The path may either be a single TOML file containing `[[advisory]]` entries, or a
+    /// directory (e.g. a checkout of the advisory-db repository) which is searched recursively
+    /// for TOML files containing one or more advisories each. Advisories are attributed to the
+    /// given `source`.
+    pub fn load(path: &Path, source: &str) -> Result<Self> {
+        let mut db = Self::default();
+
+        if path.is_dir() {
+            // Other TOML files (e.g. tool configuration) may live alongside the advisories
+            for file in find_toml_files(path)? {
+                db.add_file(&file, source, false)?;
+            }
+        } else {
+            db.add_file(path, source, true)?;
+        }
+
+        Ok(db)
+    }
+
+    /// Load project-specific advisories from a local overlay file, all of which must have IDs
+    /// starting with `LOCAL-`
+    pub fn load_overlay(path: &Path, source: &str) -> Result<Self> {
+        let db = Self::load(path, source)?;
+
+        if let Some(id) = db
+            .advisories
+            .keys()
+            .find(|id| !id.starts_with(LOCAL_ID_PREFIX))
+        {
+            return Err(Error::Parse(format!(
+                "{}: invalid advisory ID `{}` (IDs of local advisories must start with `{}`)",
+                path.display(),
+                id,
+                LOCAL_ID_PREFIX
+            )));
+        }
+
+        Ok(db)
+    }
+
+    /// Merge the advisories from another database into this one. Advisories whose IDs are
+    /// already present are skipped, so earlier sources take precedence over later ones.
+    ///
+    /// Returns the number of duplicate advisories which were skipped.
+    pub fn merge(&mut self, other: AdvisoryDatabase) -> usize {
+        let AdvisoryDatabase {
+            advisories,
+            mut sources,
+            commits,
+            ..
+        } = other;
+
+        for (source, commit) in commits {
+            self.commits.entry(source).or_insert(commit);
+        }
+
+        let mut duplicates = 0;
+
+        for (id, advisory) in advisories {
+            let source = sources.remove(&id).unwrap_or_default();
+
+            if !self.insert(advisory, &source) {
+                duplicates += 1;
+            }
+        }
+
+        duplicates
+    }
+
+    /// Iterate over all advisories in the database, ordered by ID
+    pub fn iter<'a>(&'a self) -> btree_map::Values<'a, String, Advisory> {
+        self.advisories.values()
+    }
+
+    /// Look up an advisory by its ID, or by one of its aliases (ignoring case)
+    pub fn get(&self, id: &str) -> Option<&Advisory> {
+        self.advisories.get(id).or_else(|| {
+            self.iter().find(|advisory| {
+                advisory
+                    .aliases
+                    .iter()
+                    .any(|alias| alias.eq_ignore_ascii_case(id))
+            })
+        })
+    }
+
+    /// Name of the database the given advisory was obtained from
+    pub fn source(&self, advisory: &Advisory) -> &str {
+        &self.sources[&advisory.id]
+    }
+
+    /// Commit of the git database the given advisory was obtained from, if any
+    pub fn commit(&self, advisory: &Advisory) -> Option<&str> {
+        self.commits
+            .get(self.source(advisory))
+            .map(|commit| &commit[..])
+    }
+
+    /// Record the commit which the advisories from a git database were loaded from
+    pub fn set_commit(&mut self, source: &str, commit: &str) {
+        self.commits.insert(source.to_owned(), commit.to_owned());
+    }
+
+    /// Find advisories whose ID, alias or crate name matches `query`, or whose title or
+    /// description contains it (ignoring case)
+    pub fn search(&self, query: &str) -> Vec<&Advisory> {
+        let query = query.to_lowercase();
+
+        self.iter()
+            .filter(|advisory| {
+                advisory.id.to_lowercase() == query
+                    || advisory
+                        .aliases
+                        .iter()
+                        .any(|alias| alias.to_lowercase() == query)
+                    || advisory.package.to_lowercase().contains(&query)
+                    || advisory.title.to_lowercase().contains(&query)
+                    || advisory.description.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    /// Look up advisories relevant to a particular crate
+    pub fn find_by_crate(&self, crate_name: &str) -> Vec<&Advisory> {
+        match self.crates.get(crate_name) {
+            Some(ids) => ids.iter().map(|id| &self.advisories[id]).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Find advisories that are unpatched and impact a given crate and version
+    pub fn find_vulns_for_crate(&self, crate_name: &str, version: &Version) -> Vec<&Advisory> {
+        let mut results = self.find_by_crate(crate_name);
+
+        results.retain(|advisory| advisory.is_affected(version));
+
+        results
+    }
+
+    /// Find all relevant vulnerabilities for the given lockfile
+    pub fn vulnerabilities<'a>(&'a self, lockfile: &'a Lockfile) -> Vec<Vulnerability<'a>> {
+        let mut result = vec![];
+
+        for package in &lockfile.packages {
+            for advisory in self.find_vulns_for_crate(&package.name, &package.version) {
+                result.push(Vulnerability {
+                    advisory: advisory,
+                    package: package,
+                    source: self.source(advisory),
+                    commit: self.commit(advisory),
+                })
+            }
+        }
+
+        result
+    }
+
+    /// Date of the most recent advisory in the database, in seconds since the Unix epoch
+    pub fn newest_advisory_date(&self) -> Option<u64> {
+        self.advisories
+            .values()
+            .filter_map(|advisory| advisory.date.as_ref().and_then(|d| date::parse(d)))
+            .max()
+    }
+
+    /// Number of advisories in the database
+    pub fn len(&self) -> usize {
+        self.advisories.len()
+    }
+
+    /// Add the advisories contained in a TOML file
+    fn add_file(&mut self, path: &Path, source: &str, required: bool) -> Result<()> {
+        let db_toml = files::read_to_string(path)?
+            .parse::<toml::Value>()
+            .map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))?;
+
+        if !required && db_toml.get("advisory").is_none() {
+            return Ok(());
+        }
+
+        self.add_toml_document(&db_toml, source)
+            .map_err(|e| match e {
+                Error::Parse(msg) => Error::Parse(format!("{}: {}", path.display(), msg)),
+                other => other,
+            })
+    }
+
+    /// Add the advisories contained in a TOML serialization
+    fn add_toml(&mut self, data: &str, source: &str) -> Result<()> {
+        let db_toml = data
+            .parse::<toml::Value>()
+            .map_err(|e| Error::Parse(e.to_string()))?;
+
+        self.add_toml_document(&db_toml, source)
+    }
+
+    /// Add the advisories contained in a TOML document. Both the combined
+    /// `[[advisory]]` array format and single-advisory `[advisory]` files are supported.
+    fn add_toml_document(&mut self, db_toml: &toml::Value, source: &str) -> Result<()> {
+        let advisory_toml = db_toml
+            .get("advisory")
+            .ok_or_else(|| Error::Parse("missing `advisory` attribute".to_owned()))?;
+
+        match *advisory_toml {
+            toml::Value::Array(ref arr) => {
+                for value in arr {
+                    self.add_toml_value(value, source)?;
+                }
+            }
+            ref value => self.add_toml_value(value, source)?,
+        }
+
+        Ok(())
+    }
+
+    fn add_toml_value(&mut self, value: &toml::Value, source: &str) -> Result<()> {
+        let advisory = match *value {
+            toml::Value::Table(ref table) => {
+                Advisory::from_toml_table(table).map_err(|e| match e {
+                    Error::Parse(msg) => Error::Parse(format!("invalid advisory: {}", msg)),
+                    other => other,
+                })?
+            }
+            _ => return Err(Error::Parse("`advisory` must be a table".to_owned())),
+        };
+
+        self.insert(advisory, source);
+        Ok(())
+    }
+
+    /// Add an advisory to the database. Advisories already present are kept as-is.
+    ///
+    /// Returns `false` if an advisory with the same ID was already present.
+    fn insert(&mut self, advisory: Advisory, source: &str) -> bool {
+        let entry = match self.advisories.entry(advisory.id.clone()) {
+            Occupied(_) => return false,
+            Vacant(entry) => entry,
+        };
+
+        self.crates
+            .entry(advisory.package.clone())
+            .or_insert_with(Vec::new)
+            .push(advisory.id.clone());
+        self.sources.insert(advisory.id.clone(), source.to_owned());
+
+        entry.insert(advisory);
+        true
+    }
+}
+
+/// Recursively find all TOML files beneath the given directory, skipping hidden
+/// directories such as `.git`
+pub fn find_toml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    files::find_files(dir, |path, is_dir| {
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with('.'))
+            .unwrap_or(false);
+
+        !hidden && (is_dir || path.extension().map(|ext| ext == "toml").unwrap_or(false))
+    })
+}
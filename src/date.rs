@@ -0,0 +1,61 @@
+//! Minimal handling of the `YYYY-MM-DD` dates used in advisories
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Seconds in a day
+pub const SECS_PER_DAY: u64 = 86_400;
+
+/// Parse a `YYYY-MM-DD` date into seconds since the Unix epoch (at midnight UTC)
+pub fn parse(date: &str) -> Option<u64> {
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(y), Some(m), Some(d)) if y.len() == 4 && m.len() == 2 && d.len() == 2 => (
+            y.parse::<i64>().ok()?,
+            m.parse::<u32>().ok()?,
+            d.parse::<u32>().ok()?,
+        ),
+        _ => return None,
+    };
+
+    if month < 1 || month > 12 || day < 1 || day > days_in_month(year, month) || year < 1970 {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day) as u64 * SECS_PER_DAY)
+}
+
+/// Current time in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Number of whole days elapsed since the given timestamp (zero if it is in the future)
+pub fn days_since(timestamp: u64) -> u64 {
+    now().saturating_sub(timestamp) / SECS_PER_DAY
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar for years after 1970
+/// (see http://howardhinnant.github.io/date_algorithms.html#days_from_civil)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
@@ -0,0 +1,52 @@
+//! Error types used by cargo-audit
+
+use std::{fmt, result};
+use std::error::Error as StdError;
+
+/// Errors which can occur while obtaining or evaluating the advisory database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// An error occurred performing an I/O operation (e.g. network, file)
+    IO(String),
+
+    /// Advisory database server responded with an error
+    ServerResponse(String),
+
+    /// Couldn't parse data
+    Parse(String),
+
+    /// Data failed an integrity check (digest or signature mismatch)
+    Verification(String),
+}
+
+impl Error {
+    /// Short description of the kind of error which occurred
+    fn kind(&self) -> &'static str {
+        match *self {
+            Error::IO(_) => "I/O operation failed",
+            Error::ServerResponse(_) => "invalid response",
+            Error::Parse(_) => "couldn't parse data",
+            Error::Verification(_) => "integrity check failed",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::IO(ref msg)
+            | Error::ServerResponse(ref msg)
+            | Error::Parse(ref msg)
+            | Error::Verification(ref msg) => write!(fmt, "{}: {}", self.kind(), msg),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        self.kind()
+    }
+}
+
+/// Result type used throughout cargo-audit
+pub type Result<T> = result::Result<T, Error>;
@@ -0,0 +1,192 @@
+//! Filesystem helpers shared by the modules which read advisory databases and projects
+
+use error::{Error, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Read the contents of a file
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    let mut data = vec![];
+
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut data))
+        .map_err(|e| io_error(path, e))?;
+
+    Ok(data)
+}
+
+/// Read the contents of a UTF-8 text file
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let mut data = String::new();
+
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut data))
+        .map_err(|e| io_error(path, e))?;
+
+    Ok(data)
+}
+
+/// Write `data` to a new file in `dir` whose name is unique to this process, e.g.
+/// `cargo-audit-1234-0.tmp`, returning its path.
+///
+/// Existing files (including symlinks planted by other users) are never opened, so the data
+/// can't be written anywhere else, and concurrent runs each get a file of their own.
+pub fn write_new(dir: &Path, extension: &str, data: &[u8]) -> Result<PathBuf> {
+    let mut attempt = 0;
+
+    loop {
+        let path = dir.join(format!(
+            "cargo-audit-{}-{}.{}",
+            process::id(),
+            attempt,
+            extension
+        ));
+
+        let written = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .and_then(|mut file| match file.write_all(data) {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    let _ = fs::remove_file(&path);
+                    Err(e)
+                }
+            });
+
+        match written {
+            Ok(()) => return Ok(path),
+            Err(ref e) if e.kind() == ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+            Err(e) => return Err(io_error(&path, e)),
+        }
+    }
+}
+
+/// Recursively find the files beneath `dir`, in a deterministic order.
+///
+/// `filter` is called with the path of each file and directory relative to `dir`, along with
+/// whether it is a directory. Entries it rejects are skipped, as is everything within rejected
+/// directories. Symbolic links to directories are never followed, so links pointing back up the
+/// tree can't make the walk find the same files repeatedly.
+pub fn find_files<F>(dir: &Path, filter: F) -> Result<Vec<PathBuf>>
+where
+    F: Fn(&Path, bool) -> bool,
+{
+    let mut files = vec![];
+    find_files_in(dir, Path::new(""), &filter, &mut files)?;
+    Ok(files)
+}
+
+fn find_files_in<F>(dir: &Path, relative: &Path, filter: &F, files: &mut Vec<PathBuf>) -> Result<()>
+where
+    F: Fn(&Path, bool) -> bool,
+{
+    let mut names = vec![];
+
+    for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+        names.push(entry.map_err(|e| io_error(dir, e))?.file_name());
+    }
+
+    // Sort so results (e.g. precedence between duplicate advisories) don't depend on the
+    // filesystem
+    names.sort();
+
+    for name in names {
+        let path = dir.join(&name);
+        let relative = relative.join(&name);
+        let is_dir = path.is_dir();
+
+        let is_link = fs::symlink_metadata(&path)
+            .map(|metadata| metadata.file_type().is_symlink())
+            .map_err(|e| io_error(&path, e))?;
+
+        if (is_dir && is_link) || !filter(&relative, is_dir) {
+            continue;
+        }
+
+        if is_dir {
+            find_files_in(&path, &relative, filter, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn io_error(path: &Path, err: io::Error) -> Error {
+    Error::IO(format!("{}: {}", path.display(), err))
+}
+
+/// Empty directory for a test to work in, which is removed along with its contents when
+/// dropped (including when the test panics)
+#[cfg(test)]
+#[derive(Debug)]
+pub struct ScratchDir(PathBuf);
+
+#[cfg(test)]
+impl ScratchDir {
+    /// Create a directory in the system's temporary directory, unique to this process and `name`
+    pub fn new(name: &str) -> Self {
+        let dir =
+            ::std::env::temp_dir().join(format!("cargo-audit-test-{}-{}", process::id(), name));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        ScratchDir(dir)
+    }
+}
+
+#[cfg(test)]
+impl ::std::ops::Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{find_files, ScratchDir};
+    use std::fs::{self, File};
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+
+    #[test]
+    fn symlinked_directories_not_followed() {
+        let dir = ScratchDir::new("walk");
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        File::create(dir.join("a/Cargo.lock")).unwrap();
+        File::create(dir.join("a/b/Cargo.lock")).unwrap();
+
+        // Links back up the tree, and to a file
+        symlink("..", dir.join("a/up")).unwrap();
+        symlink("../..", dir.join("a/b/up")).unwrap();
+        symlink("Cargo.lock", dir.join("a/link.lock")).unwrap();
+
+        let files: Vec<PathBuf> = find_files(&dir, |_, _| true)
+            .unwrap()
+            .iter()
+            .map(|path| path.strip_prefix(&*dir).unwrap().to_owned())
+            .collect();
+
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("a/Cargo.lock"),
+                PathBuf::from("a/b/Cargo.lock"),
+                PathBuf::from("a/link.lock"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Incremental synchronization of a local clone of the advisory-db git repository
+//!
+//! This shells out to the `git` command rather than linking against libgit2, so the
+//! user's own git configuration (credentials, proxies, etc) applies to fetches.
+
+use error::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Local clone of an advisory database git repository
+#[derive(Debug)]
+pub struct Repository {
+    path: PathBuf,
+}
+
+/// Commit of the advisory database which was checked out
+#[derive(Debug, Clone)]
+pub struct Commit {
+    /// Full commit hash
+    pub id: String,
+
+    /// Commit time in seconds since the Unix epoch
+    pub timestamp: u64,
+
+    /// First line of the commit message
+    pub summary: String,
+}
+
+impl Repository {
+    /// Use the given directory for the local clone
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Repository { path: path.into() }
+    }
+
+    /// Location of the working tree
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Fetch any new commits from `url` and check out the fetched `HEAD`, or `rev` if given.
+    ///
+    /// The local clone is created on first use. Subsequent calls only transfer the objects
+    /// which are missing locally.
+    pub fn sync(&self, url: &str, rev: Option<&str>) -> Result<Commit> {
+        if !self.path.join(".git").exists() {
+            let path = self.path.to_string_lossy().into_owned();
+            run_git(None, &["init", "--quiet", &path])?;
+        }
+
+        self.git(&["fetch", "--quiet", url, "HEAD"])?;
+        self.checkout(rev.unwrap_or("FETCH_HEAD"))
+    }
+
+    /// Check out `rev` (if given) from the local clone without fetching
+    pub fn open(&self, rev: Option<&str>) -> Result<Commit> {
+        if !self.path.join(".git").exists() {
+            return Err(Error::IO(format!(
+                "{}: no advisory-db clone found",
+                self.path.display()
+            )));
+        }
+
+        match rev {
+            Some(rev) => self.checkout(rev),
+            None => self.head(),
+        }
+    }
+
+    /// Commit which is currently checked out
+    pub fn head(&self) -> Result<Commit> {
+        let output = self.git(&["log", "-1", "--format=%H %ct %s", "HEAD"])?;
+        let mut fields = output.trim().splitn(3, ' ');
+
+        let id = fields.next().unwrap_or("").to_owned();
+        let timestamp = fields.next().and_then(|ts| ts.parse().ok());
+        let summary = fields.next().unwrap_or("").to_owned();
+
+        match timestamp {
+            Some(timestamp) if !id.is_empty() => Ok(Commit {
+                id: id,
+                timestamp: timestamp,
+                summary: summary,
+            }),
+            _ => Err(Error::Parse(format!(
+                "unexpected `git log` output: {}",
+                output
+            ))),
+        }
+    }
+
+    /// Contents of a file as of `rev`, without checking it out
+    pub fn read_file(&self, rev: &str, path: &str) -> Result<String> {
+        self.git(&["show", &format!("{}:{}", rev, path)])
+    }
+
+    fn checkout(&self, rev: &str) -> Result<Commit> {
+        self.git(&["reset", "--quiet", "--hard", rev])?;
+        self.head()
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String> {
+        run_git(Some(&self.path), args)
+    }
+}
+
+impl Commit {
+    /// Abbreviated commit hash
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(12)]
+    }
+}
+
+/// Run a git command, returning its standard output
+fn run_git(dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut command = Command::new("git");
+
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+
+    let output = command
+        .args(args)
+        .output()
+        .map_err(|e| Error::IO(format!("couldn't run git: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(Error::IO(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_git, Repository};
+    use files::ScratchDir;
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::Path;
+
+    /// Commit an advisory to the working tree at `dir` and push it to its bare remote,
+    /// returning the new commit's hash
+    fn publish(dir: &Path, id: &str) -> String {
+        let path = dir.join(format!("{}.toml", id));
+        File::create(&path)
+            .and_then(|mut file| {
+                write!(
+                    file,
+                    "[advisory]\nid = \"{}\"\npackage = \"x\"\npatched_versions = []\n\
+                     title = \"t\"\ndescription = \"d\"\n",
+                    id
+                )
+            })
+            .unwrap();
+
+        let git = |args: &[&str]| run_git(Some(dir), args).unwrap();
+        git(&["add", "."]);
+        git(&[
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--quiet",
+            "-m",
+            id,
+        ]);
+        git(&["push", "--quiet", "origin", "HEAD:refs/heads/master"]);
+        git(&["rev-parse", "HEAD"]).trim().to_owned()
+    }
+
+    #[test]
+    fn sync_from_bare_repository() {
+        let dir = ScratchDir::new("git-sync");
+        let remote = dir.join("remote.git");
+        let work = dir.join("work");
+        let url = remote.to_string_lossy().into_owned();
+
+        run_git(None, &["init", "--quiet", "--bare", &url]).unwrap();
+        run_git(None, &["init", "--quiet", &work.to_string_lossy()]).unwrap();
+        run_git(Some(&work), &["remote", "add", "origin", &url]).unwrap();
+        run_git(
+            Some(&remote),
+            &["symbolic-ref", "HEAD", "refs/heads/master"],
+        )
+        .unwrap();
+
+        let first = publish(&work, "RUSTSEC-2017-0001");
+        let repo = Repository::new(dir.join("clone"));
+
+        // The clone is created on first use
+        let commit = repo.sync(&url, None).unwrap();
+        assert_eq!(commit.id, first);
+        assert_eq!(commit.summary, "RUSTSEC-2017-0001");
+        assert!(dir.join("clone/RUSTSEC-2017-0001.toml").is_file());
+
+        // Later syncs pick up new commits
+        let second = publish(&work, "RUSTSEC-2017-0002");
+        assert_eq!(repo.sync(&url, None).unwrap().id, second);
+        assert!(dir.join("clone/RUSTSEC-2017-0002.toml").is_file());
+
+        // Earlier commits can be pinned without fetching
+        assert_eq!(repo.open(Some(&first)).unwrap().id, first);
+        assert!(!dir.join("clone/RUSTSEC-2017-0002.toml").exists());
+        assert_eq!(repo.open(None).unwrap().id, first);
+        assert_eq!(repo.sync(&url, Some(&second)).unwrap().id, second);
+    }
+
+    #[test]
+    fn open_without_clone() {
+        let dir = ScratchDir::new("git-open");
+        assert!(Repository::new(dir.join("clone")).open(None).is_err());
+    }
+}
@@ -0,0 +1,647 @@
+//! Checks for advisory TOML files, to catch mistakes before they cause parse failures or
+//! missed matches
+
+use advisory::Kind;
+use cvss::Cvss;
+use database;
+use date;
+use error::Result;
+use files;
+use hyper::Url;
+use platform;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use toml;
+use version::VersionReq;
+
+/// Fields every advisory must have
+const REQUIRED_FIELDS: &'static [&'static str] =
+    &["id", "package", "title", "description", "patched_versions"];
+
+/// Fields an advisory may have
+const OPTIONAL_FIELDS: &'static [&'static str] = &[
+    "aliases",
+    "unaffected_versions",
+    "informational",
+    "cvss",
+    "affected_arch",
+    "affected_os",
+    "affected_functions",
+    "date",
+    "withdrawn",
+    "url",
+];
+
+/// Fields containing lists of version requirements
+const VERSION_FIELDS: &'static [&'static str] = &["patched_versions", "unaffected_versions"];
+
+/// Fields containing lists of platform names, along with the names known to be valid
+const PLATFORM_FIELDS: &'static [(&'static str, &'static [&'static str])] = &[
+    ("affected_arch", platform::ARCHES),
+    ("affected_os", platform::OSES),
+];
+
+/// How serious a problem is
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Severity {
+    /// The advisory is invalid, or won't be matched correctly
+    Error,
+
+    /// The advisory is usable but probably not what was intended
+    Warning,
+}
+
+/// A problem found in an advisory file
+#[derive(Debug)]
+pub struct Problem {
+    /// File the problem was found in
+    pub path: PathBuf,
+
+    /// Line number (starting from 1) of the problem, if known
+    pub line: Option<usize>,
+
+    /// How serious the problem is
+    pub severity: Severity,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Results of linting advisory files
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Number of advisories which were checked
+    pub advisories: usize,
+
+    /// Problems found in the advisories
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    /// Number of problems with the given severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.severity == severity)
+            .count()
+    }
+}
+
+/// Check the advisories in a TOML file, or in all TOML files beneath a directory
+pub fn lint(path: &Path) -> Result<Report> {
+    let mut linter = Linter::default();
+
+    if path.is_dir() {
+        for file in database::find_toml_files(path)? {
+            linter.lint_file(&file, false)?;
+        }
+    } else {
+        linter.lint_file(path, true)?;
+    }
+
+    // Files are visited in order, but problems within each are found field by field
+    linter
+        .report
+        .problems
+        .sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+
+    Ok(linter.report)
+}
+
+#[derive(Debug, Default)]
+struct Linter {
+    report: Report,
+
+    /// Location of the first advisory seen with each ID
+    ids: HashMap<String, (PathBuf, usize)>,
+}
+
+/// Line numbers of an advisory table's header and of the keys within it
+#[derive(Debug, Default)]
+struct Lines {
+    header: usize,
+    keys: HashMap<String, usize>,
+}
+
+impl Linter {
+    /// Check the advisories in a single file. Files without advisories are ignored unless
+    /// `required` is set.
+    fn lint_file(&mut self, path: &Path, required: bool) -> Result<()> {
+        let source = files::read_to_string(path)?;
+
+        let document = match source.parse::<toml::Value>() {
+            Ok(document) => document,
+            Err(e) => {
+                let message = e.to_string();
+                self.error(path, parse_error_line(&message), message);
+                return Ok(());
+            }
+        };
+
+        let tables = match document.get("advisory") {
+            Some(&toml::Value::Array(ref tables)) => tables.iter().collect(),
+            Some(table) => vec![table],
+            None => {
+                if required {
+                    self.error(path, None, "missing `advisory` table".to_owned());
+                }
+                return Ok(());
+            }
+        };
+
+        let lines = locate_advisories(&source);
+        let unknown = Lines::default();
+
+        for (i, table) in tables.into_iter().enumerate() {
+            let lines = lines.get(i).unwrap_or(&unknown);
+            self.report.advisories += 1;
+
+            match *table {
+                toml::Value::Table(ref table) => self.lint_advisory(path, table, lines),
+                _ => self.error(path, None, "`advisory` must be a table".to_owned()),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lint_advisory(&mut self, path: &Path, table: &toml::value::Table, lines: &Lines) {
+        let line = |key: &str| lines.keys.get(key).cloned().or(Some(lines.header));
+
+        for field in REQUIRED_FIELDS {
+            if !table.contains_key(*field) {
+                let message = format!("missing required field `{}`", field);
+                self.error(path, Some(lines.header), message);
+            }
+        }
+
+        for (key, value) in table {
+            if !REQUIRED_FIELDS.contains(&&key[..]) && !OPTIONAL_FIELDS.contains(&&key[..]) {
+                let message = format!("unknown field `{}`", key);
+                self.warning(path, line(key), message);
+            } else if !VERSION_FIELDS.contains(&&key[..])
+                && !PLATFORM_FIELDS.iter().any(|&(field, _)| field == key)
+                && key != "affected_functions"
+                && key != "aliases"
+                && value.as_str().is_none()
+            {
+                self.error(path, line(key), format!("`{}` must be a string", key));
+            }
+        }
+
+        let string = |key: &str| table.get(key).and_then(|value| value.as_str());
+
+        if let Some(id) = string("id") {
+            if !is_valid_id(id) {
+                let message = format!(
+                    "invalid ID `{}` (expected RUSTSEC-YYYY-NNNN or {}<name>)",
+                    id,
+                    database::LOCAL_ID_PREFIX
+                );
+                self.error(path, line("id"), message);
+            } else if let Some(first) = self.ids.get(id).cloned() {
+                let message = format!(
+                    "duplicate ID `{}` (first used at {}:{})",
+                    id,
+                    first.0.display(),
+                    first.1
+                );
+                self.error(path, line("id"), message);
+            } else {
+                let location = (path.to_owned(), line("id").unwrap_or(0));
+                self.ids.insert(id.to_owned(), location);
+            }
+        }
+
+        if let Some(kind) = string("informational") {
+            if Kind::parse(kind).is_none() {
+                let message = format!(
+                    "unknown informational kind `{}` (expected unmaintained, unsound or notice)",
+                    kind
+                );
+                self.warning(path, line("informational"), message);
+            }
+        }
+
+        match table.get("aliases") {
+            Some(&toml::Value::Array(ref aliases)) => {
+                for alias in aliases {
+                    match alias.as_str() {
+                        Some(alias) if is_valid_alias(alias) => (),
+                        Some(alias) => {
+                            let message = format!("malformed alias `{}`", alias);
+                            self.error(path, line("aliases"), message);
+                        }
+                        None => {
+                            let message = "`aliases` must only contain strings".to_owned();
+                            self.error(path, line("aliases"), message);
+                        }
+                    }
+                }
+            }
+            Some(_) => self.error(
+                path,
+                line("aliases"),
+                "`aliases` must be an array".to_owned(),
+            ),
+            None => (),
+        }
+
+        if let Some(package) = string("package") {
+            if !is_valid_crate_name(package) {
+                let message = format!("invalid crate name `{}`", package);
+                self.error(path, line("package"), message);
+            }
+        }
+
+        for key in &["title", "description"] {
+            if string(key).map(|s| s.trim().is_empty()).unwrap_or(false) {
+                self.error(path, line(key), format!("`{}` is empty", key));
+            }
+        }
+
+        for key in VERSION_FIELDS {
+            match table.get(*key) {
+                Some(&toml::Value::Array(ref reqs)) => {
+                    for req in reqs {
+                        match req.as_str().map(|req| (req, VersionReq::parse(req))) {
+                            Some((_, Ok(_))) => (),
+                            Some((req, Err(e))) => {
+                                let message =
+                                    format!("invalid version requirement `{}`: {}", req, e);
+                                self.error(path, line(key), message);
+                            }
+                            None => {
+                                let message = format!("`{}` must only contain strings", key);
+                                self.error(path, line(key), message);
+                            }
+                        }
+                    }
+                }
+                Some(_) => self.error(path, line(key), format!("`{}` must be an array", key)),
+                None => (),
+            }
+        }
+
+        for &(key, known) in PLATFORM_FIELDS {
+            match table.get(key) {
+                Some(&toml::Value::Array(ref names)) => {
+                    for name in names {
+                        match name.as_str() {
+                            Some(name) if known.contains(&name) => (),
+                            Some(name) => {
+                                let message = format!("unknown platform `{}` in `{}`", name, key);
+                                self.warning(path, line(key), message);
+                            }
+                            None => {
+                                let message = format!("`{}` must only contain strings", key);
+                                self.error(path, line(key), message);
+                            }
+                        }
+                    }
+                }
+                Some(_) => self.error(path, line(key), format!("`{}` must be an array", key)),
+                None => (),
+            }
+        }
+
+        match table.get("affected_functions") {
+            Some(&toml::Value::Array(ref functions)) => {
+                let package = string("package").unwrap_or("").replace('-', "_");
+
+                for function in functions {
+                    match function.as_str() {
+                        Some(function) if is_valid_function_path(function, &package) => (),
+                        Some(function) => {
+                            let message = format!(
+                                "invalid function path `{}` (expected `{}::<path>`)",
+                                function, package
+                            );
+                            self.error(path, line("affected_functions"), message);
+                        }
+                        None => {
+                            let message = "`affected_functions` must only contain strings";
+                            self.error(path, line("affected_functions"), message.to_owned());
+                        }
+                    }
+                }
+            }
+            Some(_) => {
+                let message = "`affected_functions` must be an array".to_owned();
+                self.error(path, line("affected_functions"), message);
+            }
+            None => (),
+        }
+
+        if let Some(vector) = string("cvss") {
+            if let Err(e) = Cvss::parse(vector) {
+                let message = format!("invalid CVSS vector `{}`: {}", vector, e);
+                self.error(path, line("cvss"), message);
+            }
+        }
+
+        for key in &["date", "withdrawn"] {
+            if let Some(date) = string(key) {
+                if date::parse(date).is_none() {
+                    let message = format!("invalid date `{}` (expected YYYY-MM-DD)", date);
+                    self.error(path, line(key), message);
+                }
+            }
+        }
+
+        if let Some(url) = string("url") {
+            match Url::parse(url) {
+                Ok(ref parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => (),
+                Ok(_) => {
+                    let message = format!("URL `{}` must use http or https", url);
+                    self.error(path, line("url"), message);
+                }
+                Err(e) => {
+                    let message = format!("invalid URL `{}`: {}", url, e);
+                    self.error(path, line("url"), message);
+                }
+            }
+        }
+    }
+
+    fn error(&mut self, path: &Path, line: Option<usize>, message: String) {
+        self.add(path, line, Severity::Error, message);
+    }
+
+    fn warning(&mut self, path: &Path, line: Option<usize>, message: String) {
+        self.add(path, line, Severity::Warning, message);
+    }
+
+    fn add(&mut self, path: &Path, line: Option<usize>, severity: Severity, message: String) {
+        self.report.problems.push(Problem {
+            path: path.to_owned(),
+            line: line,
+            severity: severity,
+            message: message,
+        });
+    }
+}
+
+/// Advisory IDs have the form `RUSTSEC-YYYY-NNNN`, or `LOCAL-<name>` for project-specific
+/// advisories
+fn is_valid_id(id: &str) -> bool {
+    if id.starts_with(database::LOCAL_ID_PREFIX) {
+        let name = &id[database::LOCAL_ID_PREFIX.len()..];
+
+        return !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    }
+
+    let parts: Vec<_> = id.split('-').collect();
+
+    parts.len() == 3
+        && parts[0] == "RUSTSEC"
+        && parts[1].len() == 4
+        && parts[2].len() == 4
+        && parts[1..]
+            .iter()
+            .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Aliases are IDs from other databases. CVE (`CVE-YYYY-NNNN...`) and GitHub
+/// (`GHSA-xxxx-xxxx-xxxx`) IDs are checked for the right format; anything else just has to
+/// look like an ID.
+fn is_valid_alias(alias: &str) -> bool {
+    let parts: Vec<_> = alias.split('-').collect();
+
+    match parts[0] {
+        "CVE" => {
+            parts.len() == 3
+                && parts[1].len() == 4
+                && parts[2].len() >= 4
+                && parts[1..]
+                    .iter()
+                    .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+        }
+        "GHSA" => {
+            parts.len() == 4
+                && parts[1..]
+                    .iter()
+                    .all(|part| part.len() == 4 && part.chars().all(|c| c.is_ascii_alphanumeric()))
+        }
+        _ => {
+            parts.len() >= 2
+                && alias
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        }
+    }
+}
+
+/// Crate names are ASCII alphanumerics, `-` and `_`, starting with a letter
+fn is_valid_crate_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic())
+        .unwrap_or(false)
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Function paths are Rust paths of at least two segments, starting with the crate's name
+/// (with any `-` replaced by `_`), e.g. `foo::bar::parse_unchecked`
+fn is_valid_function_path(function: &str, package: &str) -> bool {
+    let segments: Vec<_> = function.split("::").collect();
+
+    segments.len() >= 2
+        && segments[0] == package
+        && segments.iter().all(|segment| {
+            segment
+                .chars()
+                .next()
+                .map(|c| c.is_alphabetic() || c == '_')
+                .unwrap_or(false)
+                && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+}
+
+/// Line number of a TOML parse error, which the parser only includes in its message
+fn parse_error_line(message: &str) -> Option<usize> {
+    message
+        .rfind(" at line ")
+        .and_then(|pos| message[pos + " at line ".len()..].trim().parse().ok())
+}
+
+/// Find the lines of each advisory table in a TOML document, and of the keys within them.
+///
+/// The TOML parser doesn't keep track of positions, so this scans the source text for table
+/// headers and `key = value` lines, skipping over multi-line strings.
+fn locate_advisories(source: &str) -> Vec<Lines> {
+    let mut advisories = vec![];
+    let mut in_advisory = false;
+    let mut in_string = false;
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+
+        // An odd number of delimiters opens or closes a multi-line string. Lines inside (or
+        // closing) one can't contain keys.
+        let delimiters = line.matches("\"\"\"").count() + line.matches("\'\'\'").count();
+        let was_in_string = in_string;
+
+        if delimiters % 2 == 1 {
+            in_string = !in_string;
+        }
+
+        if was_in_string {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            let header = line.trim_matches(|c| c == '[' || c == ']').trim();
+            in_advisory = header == "advisory";
+
+            if in_advisory {
+                advisories.push(Lines {
+                    header: i + 1,
+                    keys: HashMap::new(),
+                });
+            }
+        } else if in_advisory {
+            let key = line
+                .splitn(2, '=')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .trim_matches('"');
+            let is_key = line.contains('=')
+                && !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+
+            if is_key {
+                if let Some(lines) = advisories.last_mut() {
+                    lines.keys.entry(key.to_owned()).or_insert(i + 1);
+                }
+            }
+        }
+    }
+
+    advisories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_valid_alias, is_valid_function_path, is_valid_id, locate_advisories, parse_error_line,
+        Linter,
+    };
+    use std::path::Path;
+    use toml;
+
+    const ADVISORY: &'static str = r#"[advisory]
+id = "RUSTSEC-2017-0001"
+package = "heffalump"
+title = "Woozles"
+description = """
+Text which looks like a key:
+id = "LOCAL-not-a-key"
+"""
+patched_versions = [">= 1.1.0"]
+"#;
+
+    #[test]
+    fn keys_in_multi_line_strings_ignored() {
+        let lines = locate_advisories(ADVISORY);
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].header, 1);
+        assert_eq!(lines[0].keys["id"], 2);
+        assert_eq!(lines[0].keys["description"], 5);
+        assert_eq!(lines[0].keys["patched_versions"], 9);
+        assert_eq!(lines[0].keys.len(), 5);
+    }
+
+    #[test]
+    fn array_of_tables_headers() {
+        let source = "[[advisory]]\nid = \"a\"\n\n[other]\nkey = 1\n\n[[ advisory ]]\n'''\nx = 1\n'''\nid = \"b\"\n";
+        let lines = locate_advisories(source);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].header, lines[0].keys["id"]), (1, 2));
+        assert!(!lines[0].keys.contains_key("key"));
+        assert_eq!((lines[1].header, lines[1].keys["id"]), (7, 11));
+        assert!(!lines[1].keys.contains_key("x"));
+    }
+
+    #[test]
+    fn duplicate_ids_across_files() {
+        let document = ADVISORY.parse::<toml::Value>().unwrap();
+        let table = document["advisory"].as_table().unwrap();
+        let lines = &locate_advisories(ADVISORY)[0];
+        let mut linter = Linter::default();
+
+        linter.lint_advisory(Path::new("a.toml"), table, lines);
+        assert!(linter.report.problems.is_empty());
+
+        linter.lint_advisory(Path::new("b.toml"), table, lines);
+        let problems = &linter.report.problems;
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].path, Path::new("b.toml"));
+        assert_eq!(problems[0].line, Some(2));
+        assert_eq!(
+            problems[0].message,
+            "duplicate ID `RUSTSEC-2017-0001` (first used at a.toml:2)"
+        );
+    }
+
+    #[test]
+    fn ids() {
+        assert!(is_valid_id("RUSTSEC-2017-0001"));
+        assert!(is_valid_id("LOCAL-internal_crate.1"));
+        assert!(!is_valid_id("LOCAL-"));
+        assert!(!is_valid_id("LOCAL-a b"));
+        assert!(!is_valid_id("RUSTSEC-17-0001"));
+        assert!(!is_valid_id("RUSTSEC-2017-001"));
+        assert!(!is_valid_id("RUSTSEC-2017-000a"));
+        assert!(!is_valid_id("RUSTSEC-2017-0001-1"));
+        assert!(!is_valid_id("CVE-2017-0001"));
+    }
+
+    #[test]
+    fn aliases() {
+        assert!(is_valid_alias("CVE-2017-1000"));
+        assert!(is_valid_alias("CVE-2017-100000"));
+        assert!(!is_valid_alias("CVE-2017-100"));
+        assert!(!is_valid_alias("CVE-17-1000"));
+        assert!(is_valid_alias("GHSA-abcd-1234-wxyz"));
+        assert!(!is_valid_alias("GHSA-abcd-1234"));
+        assert!(!is_valid_alias("GHSA-abcd-12345-wxyz"));
+        assert!(is_valid_alias("OSV-2020-123"));
+        assert!(!is_valid_alias("OSV"));
+        assert!(!is_valid_alias("OSV 2020"));
+    }
+
+    #[test]
+    fn function_paths() {
+        assert!(is_valid_function_path("foo_bar::parse", "foo_bar"));
+        assert!(is_valid_function_path("foo_bar::de::_parse2", "foo_bar"));
+        assert!(!is_valid_function_path("foo_bar", "foo_bar"));
+        assert!(!is_valid_function_path("foo-bar::parse", "foo_bar"));
+        assert!(!is_valid_function_path("other::parse", "foo_bar"));
+        assert!(!is_valid_function_path("foo_bar::", "foo_bar"));
+        assert!(!is_valid_function_path("foo_bar::2parse", "foo_bar"));
+        assert!(!is_valid_function_path("foo_bar::Vec<u8>::new", "foo_bar"));
+    }
+
+    #[test]
+    fn parse_error_lines() {
+        let message = "[advisory]\nid = \n"
+            .parse::<toml::Value>()
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(parse_error_line(&message), Some(2));
+        assert_eq!(parse_error_line("expected a value at line 12"), Some(12));
+        assert_eq!(parse_error_line("expected a value"), None);
+    }
+}
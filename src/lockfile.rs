@@ -0,0 +1,350 @@
+//! Parser for `Cargo.lock` files
+
+use error::{Error, Result};
+use files;
+use semver::Version;
+use std::path::Path;
+use std::ptr;
+use toml;
+
+/// Parsed Cargo.lock file containing dependencies
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lockfile {
+    /// Dependencies enumerated in the lockfile
+    pub packages: Vec<Package>,
+}
+
+/// A package locked to a particular version
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    /// Name of the crate
+    pub name: String,
+
+    /// Locked version of the crate
+    pub version: Version,
+
+    /// Where the crate comes from (e.g. `registry+https://github.com/rust-lang/crates.io-index`),
+    /// or `None` for crates within the workspace
+    pub source: Option<String>,
+
+    /// Packages this one depends on
+    pub dependencies: Vec<Dependency>,
+}
+
+/// A reference from one package to another it depends on. Newer lockfiles only give the version
+/// and source where they're needed to tell packages with the same name apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    /// Name of the crate
+    pub name: String,
+
+    /// Locked version of the crate, if given
+    pub version: Option<Version>,
+
+    /// Where the crate comes from, if given
+    pub source: Option<String>,
+}
+
+impl Lockfile {
+    /// Load a lockfile from disk
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let data = files::read_to_string(path)?;
+
+        Self::from_toml(&data).map_err(|e| match e {
+            Error::Parse(msg) => Error::Parse(format!("{}: {}", path.display(), msg)),
+            e => e,
+        })
+    }
+
+    /// Parse a lockfile from a TOML string
+    pub fn from_toml(data: &str) -> Result<Self> {
+        let document = data
+            .parse::<toml::Value>()
+            .map_err(|e| Error::Parse(e.to_string()))?;
+
+        let tables = match document.get("package") {
+            Some(&toml::Value::Array(ref tables)) => tables,
+            Some(_) => return Err(Error::Parse("`package` must be an array".to_owned())),
+            None => return Ok(Lockfile { packages: vec![] }),
+        };
+
+        let mut packages = tables
+            .iter()
+            .map(|table| match *table {
+                toml::Value::Table(ref table) => Package::from_toml_table(table),
+                _ => Err(Error::Parse("`package` must be a table".to_owned())),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Older lockfiles list the root crate separately
+        if let Some(&toml::Value::Table(ref root)) = document.get("root") {
+            packages.insert(0, Package::from_toml_table(root)?);
+        }
+
+        Ok(Lockfile { packages: packages })
+    }
+
+    /// Packages which depend directly on the given one
+    pub fn dependents(&self, package: &Package) -> Vec<&Package> {
+        self.packages
+            .iter()
+            .filter(|p| p.dependencies.iter().any(|dep| dep.matches(package)))
+            .collect()
+    }
+
+    /// Chains of dependents leading from a package to each of the root crates it's pulled in
+    /// by, i.e. those which nothing else depends on. Each path starts with the package itself.
+    ///
+    /// The number of paths can grow exponentially in large graphs, so at most `limit` are
+    /// found. Also returns whether any were left out.
+    pub fn dependency_paths<'a>(
+        &'a self,
+        package: &'a Package,
+        limit: usize,
+    ) -> (Vec<Vec<&'a Package>>, bool) {
+        let mut paths = vec![];
+        let truncated = self.find_paths(&mut vec![package], &mut paths, limit);
+        (paths, truncated)
+    }
+
+    /// Extend `path` to every root crate above its last package, returning whether the limit
+    /// was reached
+    fn find_paths<'a>(
+        &'a self,
+        path: &mut Vec<&'a Package>,
+        paths: &mut Vec<Vec<&'a Package>>,
+        limit: usize,
+    ) -> bool {
+        // Dev-dependencies can form cycles, which end a path where they'd repeat a package
+        let dependents: Vec<_> = self
+            .dependents(path[path.len() - 1])
+            .into_iter()
+            .filter(|&dependent| !path.iter().any(|&p| ptr::eq(p, dependent)))
+            .collect();
+
+        if dependents.is_empty() {
+            if paths.len() == limit {
+                return true;
+            }
+
+            paths.push(path.clone());
+            return false;
+        }
+
+        for dependent in dependents {
+            path.push(dependent);
+            let truncated = self.find_paths(path, paths, limit);
+            path.pop();
+
+            if truncated {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl Package {
+    fn from_toml_table(table: &toml::value::Table) -> Result<Self> {
+        let string = |key: &str| match table.get(key) {
+            Some(value) => value
+                .as_str()
+                .map(Some)
+                .ok_or_else(|| Error::Parse(format!("package `{}` must be a string", key))),
+            None => Ok(None),
+        };
+
+        let name =
+            string("name")?.ok_or_else(|| Error::Parse("package is missing `name`".to_owned()))?;
+
+        let version = string("version")?
+            .ok_or_else(|| Error::Parse(format!("package `{}` is missing `version`", name)))?;
+
+        Ok(Package {
+            name: name.to_owned(),
+            version: Version::parse(version).map_err(|e| {
+                Error::Parse(format!(
+                    "invalid version `{}` of `{}`: {}",
+                    version, name, e
+                ))
+            })?,
+            source: string("source")?.map(|source| source.to_owned()),
+            dependencies: match table.get("dependencies") {
+                Some(&toml::Value::Array(ref dependencies)) => dependencies
+                    .iter()
+                    .map(|dependency| match dependency.as_str() {
+                        Some(dependency) => Dependency::parse(dependency),
+                        None => Err(Error::Parse(format!(
+                            "dependencies of `{}` must be strings",
+                            name
+                        ))),
+                    })
+                    .collect::<Result<_>>()?,
+                Some(_) => {
+                    return Err(Error::Parse(format!(
+                        "dependencies of `{}` must be an array",
+                        name
+                    )))
+                }
+                None => vec![],
+            },
+        })
+    }
+
+    /// Is this package obtained from a registry (as opposed to git, or a path)?
+    pub fn is_registry(&self) -> bool {
+        self.source
+            .as_ref()
+            .map(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+            .unwrap_or(false)
+    }
+}
+
+impl Dependency {
+    /// Parse a dependency as written in a lockfile: `name`, `name version` or
+    /// `name version (source)`
+    pub fn parse(dependency: &str) -> Result<Self> {
+        let mut parts = dependency.splitn(3, ' ');
+        let name = parts.next().unwrap_or("");
+
+        if name.is_empty() {
+            return Err(Error::Parse(format!("invalid dependency `{}`", dependency)));
+        }
+
+        let version = match parts.next() {
+            Some(version) => Some(Version::parse(version).map_err(|e| {
+                Error::Parse(format!(
+                    "invalid version in dependency `{}`: {}",
+                    dependency, e
+                ))
+            })?),
+            None => None,
+        };
+
+        let source = match parts.next() {
+            Some(source) if source.starts_with('(') && source.ends_with(')') => {
+                Some(source[1..source.len() - 1].to_owned())
+            }
+            Some(_) => return Err(Error::Parse(format!("invalid dependency `{}`", dependency))),
+            None => None,
+        };
+
+        Ok(Dependency {
+            name: name.to_owned(),
+            version: version,
+            source: source,
+        })
+    }
+
+    /// Does this dependency refer to the given package?
+    pub fn matches(&self, package: &Package) -> bool {
+        self.name == package.name
+            && self
+                .version
+                .as_ref()
+                .map(|version| *version == package.version)
+                .unwrap_or(true)
+            && self
+                .source
+                .as_ref()
+                .map(|source| package.source.as_ref() == Some(source))
+                .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lockfile, Package};
+
+    fn lockfile() -> Lockfile {
+        Lockfile::from_toml(include_str!("../tests/support/dependents.lock")).unwrap()
+    }
+
+    fn package<'a>(lockfile: &'a Lockfile, name: &str, version: &str) -> &'a Package {
+        lockfile
+            .packages
+            .iter()
+            .find(|p| p.name == name && p.version.to_string() == version)
+            .unwrap()
+    }
+
+    fn names(packages: &[&Package]) -> Vec<String> {
+        packages
+            .iter()
+            .map(|p| format!("{} {}", p.name, p.version))
+            .collect()
+    }
+
+    #[test]
+    fn dependents_by_version() {
+        let lockfile = lockfile();
+
+        let b1 = package(&lockfile, "b", "1.0.0");
+        assert_eq!(
+            names(&lockfile.dependents(b1)),
+            vec!["app 0.1.0", "a 0.1.0"]
+        );
+
+        let b2 = package(&lockfile, "b", "2.0.0");
+        assert_eq!(names(&lockfile.dependents(b2)), vec!["a 0.1.0"]);
+
+        // Name-only entries match the only package with that name
+        let a = package(&lockfile, "a", "0.1.0");
+        assert_eq!(names(&lockfile.dependents(a)), vec!["app 0.1.0"]);
+
+        let vuln = package(&lockfile, "vuln", "1.0.0");
+        assert_eq!(
+            names(&lockfile.dependents(vuln)),
+            vec!["b 1.0.0", "b 2.0.0", "tests-helper 0.1.0"]
+        );
+    }
+
+    #[test]
+    fn dependency_paths() {
+        let lockfile = lockfile();
+        let vuln = package(&lockfile, "vuln", "1.0.0");
+        let (paths, truncated) = lockfile.dependency_paths(vuln, 10);
+
+        let paths: Vec<_> = paths.iter().map(|path| names(path)).collect();
+        assert_eq!(
+            paths,
+            vec![
+                vec!["vuln 1.0.0", "b 1.0.0", "app 0.1.0"],
+                vec!["vuln 1.0.0", "b 1.0.0", "a 0.1.0", "app 0.1.0"],
+                vec!["vuln 1.0.0", "b 2.0.0", "a 0.1.0", "app 0.1.0"],
+                // The cycle back to `vuln` is cut off
+                vec!["vuln 1.0.0", "tests-helper 0.1.0"],
+            ]
+        );
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn dependency_paths_limit() {
+        let lockfile = lockfile();
+        let vuln = package(&lockfile, "vuln", "1.0.0");
+
+        let (paths, truncated) = lockfile.dependency_paths(vuln, 4);
+        assert_eq!((paths.len(), truncated), (4, false));
+
+        let (paths, truncated) = lockfile.dependency_paths(vuln, 2);
+        assert_eq!((paths.len(), truncated), (2, true));
+        assert_eq!(
+            names(&paths[1]),
+            vec!["vuln 1.0.0", "b 1.0.0", "a 0.1.0", "app 0.1.0"]
+        );
+    }
+
+    #[test]
+    fn root_crate_paths() {
+        let lockfile = lockfile();
+        let app = package(&lockfile, "app", "0.1.0");
+        let (paths, truncated) = lockfile.dependency_paths(app, 10);
+
+        assert_eq!(paths.len(), 1);
+        assert_eq!(names(&paths[0]), vec!["app 0.1.0"]);
+        assert!(!truncated);
+    }
+}
@@ -6,137 +6,692 @@
 #![deny(trivial_casts, trivial_numeric_casts)]
 #![deny(unsafe_code, unstable_features, unused_import_braces, unused_qualifications)]
 
+mod advisory;
+mod browse;
+mod cache;
+mod config;
+mod cvss;
+mod database;
+mod date;
+mod error;
+mod fetch;
+mod files;
+mod git;
+mod lint;
+mod lockfile;
+mod output;
+mod platform;
+mod reachability;
+mod registry;
 mod shell;
+mod verify;
+mod version;
+mod workspace;
 
+extern crate base64;
 extern crate clap;
+extern crate hyper;
+extern crate hyper_native_tls;
 extern crate isatty;
-extern crate rustsec;
+extern crate native_tls;
+extern crate semver;
+extern crate semver_parser;
 #[macro_use]
 extern crate serde_json;
+extern crate sha2;
 extern crate term;
+extern crate toml;
 
-use clap::{App, Arg, SubCommand};
-use rustsec::{AdvisoryDatabase, Lockfile};
-use rustsec::advisory::Advisory;
-use rustsec::error::Error as RustSecError;
-use rustsec::lockfile::Package;
+use advisory::{Advisory, Kind};
+use cache::Cache;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use config::Config;
+use cvss::Severity;
+use database::{AdvisoryDatabase, Vulnerability};
+use error::Error;
+use fetch::{FetchOptions, Fetcher};
+use git::Repository;
+use lockfile::{Lockfile, Package};
+use output::{
+    affected_versions, colored_attribute, cvss_json, priority, severity, severity_color,
+    OutputFormat,
+};
+use platform::Target;
+use reachability::{Reachability, SourceIndex};
+use registry::RegistryIndex;
 use shell::{ColorConfig, Shell};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use term::color::{GREEN, RED, WHITE};
+use std::ptr;
+use term::color::{CYAN, GREEN, RED, WHITE, YELLOW};
+use verify::Integrity;
 
-enum OutputFormat {
-    Text,
-    Json,
+/// Exit status when the advisory database is older than `--max-db-age` and `--deny-stale-db`
+/// was given
+const EXIT_STALE_DATABASE: i32 = 2;
+
+/// Exit status when an advisory database couldn't be fetched and no cached copy is available
+const EXIT_FETCH_FAILED: i32 = 3;
+
+/// URL where the TOML file containing the advisory database is located
+const ADVISORY_DB_URL: &'static str =
+    "https://raw.githubusercontent.com/RustSec/advisory-db/master/Advisories.toml";
+
+/// Location of project-specific advisories, relative to the project root
+const OVERLAY_PATH: &'static str = ".cargo/audit-advisories.toml";
+
+/// Maximum number of paths shown from a vulnerable crate to the root crates depending on it
+const MAX_DEPENDENCY_PATHS: usize = 16;
+
+/// Location from which the advisory database is obtained
+enum Source<'a> {
+    /// Remote TOML file fetched over HTTP(S)
+    Url(&'a str),
+
+    /// Local TOML file or directory of advisories
+    Path(&'a str),
+
+    /// Git repository which is cloned and incrementally fetched
+    Git(&'a str),
+}
+
+/// Options controlling how advisory databases are obtained
+struct DatabaseOptions<'a> {
+    /// Only use previously fetched databases
+    no_fetch: bool,
+
+    /// Commit to check out from the git advisory database
+    git_rev: Option<&'a str>,
+
+    /// Integrity checks to perform before a database is used
+    integrity: Integrity<'a>,
+}
+
+/// Network client which is only set up when something actually has to be fetched, so broken
+/// network settings can't fail audits of local databases
+struct LazyFetcher<'a> {
+    matches: &'a ArgMatches<'a>,
+    fetcher: Option<Fetcher>,
+}
+
+/// A lockfile to audit, and the advisories which apply to its project
+struct Project {
+    /// Path of the lockfile, as displayed
+    filename: String,
+
+    /// Root directory of the project (or workspace)
+    dir: PathBuf,
+
+    /// Packages in the lockfile
+    lockfile: Lockfile,
+
+    /// Advisories from the databases, along with the project's local advisories
+    advisory_db: AdvisoryDatabase,
+}
+
+/// Findings from auditing a single lockfile
+struct Report<'a> {
+    /// The project the lockfile belongs to
+    project: &'a Project,
+
+    /// Advisories affecting the target platforms
+    vulnerabilities: Vec<Vulnerability<'a>>,
+
+    /// Advisories only affecting other platforms
+    inapplicable: Vec<Vulnerability<'a>>,
+
+    /// Advisories which have been withdrawn (unless `--include-withdrawn` was given)
+    withdrawn: Vec<Vulnerability<'a>>,
+
+    /// Packages whose locked versions have been yanked
+    yanked: Vec<&'a Package>,
+
+    /// Identifiers used in the project's sources, if any advisory lists affected functions
+    sources: Option<SourceIndex>,
+}
+
+impl<'a> Source<'a> {
+    /// Name used to attribute advisories to this source
+    fn name(&self) -> &'a str {
+        match *self {
+            Source::Url(name) | Source::Path(name) | Source::Git(name) => name,
+        }
+    }
 }
 
 fn main() {
+    let db_command = |name, about| {
+        SubCommand::with_name(name)
+            .about(about)
+            .args(&database_args())
+            .args(&output_args())
+    };
+
     let matches = App::new("cargo")
         .subcommand(
             SubCommand::with_name("audit")
                 .version(env!("CARGO_PKG_VERSION"))
                 .author("Tony Arcieri <bascule@gmail.com>")
                 .about("Audit Cargo.lock for crates with security vulnerabilities.")
+                .setting(AppSettings::ArgsNegateSubcommands)
+                .arg(
+                    Arg::from_usage(
+                        "-f, --file=[NAME]... 'Cargo lockfiles to inspect (default: the workspace's)'",
+                    )
+                    .number_of_values(1),
+                )
+                .arg(
+                    Arg::from_usage("--manifest-path=[PATH] 'Path to Cargo.toml'")
+                        .conflicts_with("file"),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "--recursive=[DIR] 'Inspect every lockfile beneath this directory'",
+                    )
+                    .conflicts_with_all(&["file", "manifest-path"]),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "--exclude=[GLOB]... 'Skip paths matching this glob with --recursive'",
+                    )
+                    .number_of_values(1)
+                    .requires("recursive"),
+                )
                 .arg_from_usage(
-                    "-f, --file=[NAME] 'Cargo lockfile to inspect (default: Cargo.lock)'",
+                    "--generate-lockfile 'Run `cargo generate-lockfile` if there's no lockfile'",
                 )
-                .arg_from_usage("-u, --url=[URL] 'URL from which to fetch advisory database'")
                 .arg(
-                    Arg::from_usage("--color=[COLOR] Colored output")
-                        .possible_values(&["auto", "always", "never"]),
+                    Arg::from_usage(
+                        "--keep-lockfile 'Keep the lockfile made by --generate-lockfile'",
+                    )
+                    .requires("generate-lockfile"),
                 )
                 .arg(
-                    Arg::from_usage("--format=[FORMAT] Output Format")
-                        .possible_values(&["text", "json"]),
+                    Arg::from_usage(
+                        "--target=[TRIPLE]... 'Platform the project is built for (default: host)'",
+                    )
+                    .number_of_values(1),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "-D, --deny=[KIND]... 'Also fail on informational advisories of this kind'",
+                    )
+                    .possible_values(&["unmaintained", "unsound", "notice", "yanked", "warnings"])
+                    .number_of_values(1),
+                )
+                .arg(
+                    Arg::from_usage(
+                        "--min-severity=[LEVEL] 'Only fail on findings at least this severe'",
+                    )
+                    .possible_values(&["none", "low", "medium", "high", "critical"]),
+                )
+                .arg_from_usage("--include-withdrawn 'Report advisories which have been withdrawn'")
+                .arg_from_usage(
+                    "--index=[PATH] 'crates.io index to check for yanked crates (default: cargo's)'",
+                )
+                .args(&database_args())
+                .args(&output_args())
+                .subcommand(
+                    SubCommand::with_name("db")
+                        .about("Browse the advisory database")
+                        .setting(AppSettings::SubcommandRequiredElseHelp)
+                        .subcommand(db_command("list", "List all advisories"))
+                        .subcommand(
+                            db_command("show", "Show the details of an advisory")
+                                .arg_from_usage("<ID> 'Advisory ID (e.g. RUSTSEC-2017-0001)'"),
+                        )
+                        .subcommand(
+                            db_command("search", "Search advisories by crate or keyword")
+                                .arg_from_usage("<QUERY> 'Crate name or keyword'"),
+                        )
+                        .subcommand(
+                            SubCommand::with_name("lint")
+                                .about("Check advisory files for mistakes")
+                                .arg_from_usage("<PATH> 'Advisory TOML file or directory'")
+                                .args(&output_args()),
+                        ),
                 ),
         )
         .get_matches();
 
-    let (filename, url, color_config, output_format) =
-        if let Some(audit_matches) = matches.subcommand_matches("audit") {
-            (
-                audit_matches.value_of("file").unwrap_or("Cargo.lock"),
-                audit_matches
-                    .value_of("url")
-                    .unwrap_or(rustsec::ADVISORY_DB_URL),
-                audit_matches.value_of("color").unwrap_or("auto"),
-                audit_matches.value_of("format").unwrap_or("text"),
-            )
-        } else {
-            panic!("cargo-audit is intended to be invoked as a cargo subcommand");
-        };
+    let audit_matches = match matches.subcommand_matches("audit") {
+        Some(audit_matches) => audit_matches,
+        None => panic!("cargo-audit is intended to be invoked as a cargo subcommand"),
+    };
 
-    let mut shell = shell::create(match color_config {
-        "always" => ColorConfig::Always,
-        "never" => ColorConfig::Never,
-        _ => ColorConfig::Auto,
-    });
+    match audit_matches.subcommand() {
+        ("db", Some(db_matches)) => match db_matches.subcommand() {
+            ("lint", Some(lint_matches)) => lint_db(lint_matches),
+            (command, Some(command_matches)) => browse_db(command, command_matches),
+            _ => unreachable!(),
+        },
+        _ => audit(audit_matches),
+    }
+}
 
-    let output_format = match output_format {
-        "text" => OutputFormat::Text,
-        "json" => OutputFormat::Json,
-        _ => OutputFormat::Text,
-    };
+/// Options for obtaining the advisory database, accepted by all commands
+fn database_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::from_usage("-u, --url=[URL]... 'URL from which to fetch advisory database'")
+            .number_of_values(1),
+        Arg::from_usage("--db=[PATH]... 'Local advisory database (TOML file or directory)'")
+            .number_of_values(1),
+        Arg::from_usage("--git-url=[URL]... 'Sync an advisory database from a git repository'")
+            .number_of_values(1),
+        Arg::from_usage("--db-rev=[REV] 'Commit of the git advisory database to use'")
+            .requires("git-url"),
+        Arg::from_usage("--no-fetch 'Use the cached advisory database'").alias("offline"),
+        Arg::from_usage("--db-sha256=[DIGEST]... 'Expected SHA-256 of the database'")
+            .number_of_values(1),
+        Arg::from_usage("--keyring=[FILE] 'Keyring for verifying database signatures'"),
+        Arg::from_usage("--db-signature=[LOCATION] 'Database signature (default: <db>.asc)'")
+            .requires("keyring"),
+        Arg::from_usage("--max-db-age=[DAYS] 'Warn if the advisory database is older than this'")
+            .validator(is_number),
+        Arg::from_usage("--deny-stale-db 'Fail if the database exceeds --max-db-age'")
+            .requires("max-db-age"),
+        Arg::from_usage("--proxy=[URL] 'HTTP proxy for fetching advisory databases'"),
+        Arg::from_usage("--ca-cert=[PEM] 'Additional trusted root certificates'"),
+        Arg::from_usage("--fetch-timeout=[SECS] 'Network timeout (default: 30)'")
+            .validator(is_number),
+        Arg::from_usage("--fetch-retries=[N] 'Retries for failed fetches (default: 2)'")
+            .validator(is_number),
+    ]
+}
 
-    let lockfile = match Lockfile::load(filename) {
-        Ok(lf) => lf,
-        Err(RustSecError::IO) => {
-            not_found(&mut shell, filename).unwrap();
-            exit(1);
+/// Options controlling how results are displayed, accepted by all commands
+fn output_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::from_usage("--color=[COLOR] Colored output")
+            .possible_values(&["auto", "always", "never"]),
+        Arg::from_usage("--format=[FORMAT] Output Format").possible_values(&["text", "json"]),
+    ]
+}
+
+/// Audit lockfiles against the advisory database (`cargo audit`)
+fn audit(matches: &ArgMatches) {
+    let (mut shell, output_format) = output_options(matches);
+
+    // Like cargo, find the lockfile at the root of the workspace
+    let lockfile_paths = match (matches.values_of("file"), matches.value_of("recursive")) {
+        (Some(files), _) => files.map(PathBuf::from).collect(),
+        (None, Some(dir)) => {
+            let exclude: Vec<_> = matches
+                .values_of("exclude")
+                .map(|globs| globs.collect())
+                .unwrap_or_else(Vec::new);
+
+            match workspace::find_lockfiles(Path::new(dir), &exclude) {
+                Ok(ref paths) if paths.is_empty() => {
+                    shell
+                        .say_status(
+                            "error:",
+                            format!("no lockfiles found beneath {}", dir),
+                            RED,
+                            false,
+                        )
+                        .unwrap();
+                    exit(1);
+                }
+                Ok(paths) => paths,
+                Err(e) => {
+                    shell.say_status("error:", e, RED, false).unwrap();
+                    exit(1);
+                }
+            }
+        }
+        (None, None) => {
+            match workspace::find_lockfile(matches.value_of("manifest-path").map(Path::new)) {
+                Ok(Some(path)) => vec![path],
+                Ok(None) => vec![PathBuf::from("Cargo.lock")],
+                Err(e) => {
+                    shell.say_status("error:", e, RED, false).unwrap();
+                    exit(1);
+                }
+            }
         }
-        Err(ex) => panic!("Couldn't load {}: {}", filename, ex),
     };
 
-    if let OutputFormat::Text = output_format {
-        shell
-            .say_status("Fetching", &format!("advisories `{}`", url), GREEN, true)
-            .unwrap();
-    }
+    let targets = match matches.values_of("target") {
+        Some(triples) => match triples.map(Target::parse).collect::<Result<Vec<_>, _>>() {
+            Ok(targets) => targets,
+            Err(e) => {
+                shell.say_status("error:", e, RED, false).unwrap();
+                exit(1);
+            }
+        },
+        None => vec![Target::host()],
+    };
 
-    let advisory_db =
-        AdvisoryDatabase::fetch_from_url(url).expect("Couldn't fetch advisory database");
-    if let OutputFormat::Text = output_format {
-        shell
-            .say_status(
-                "Scanning",
-                &format!(
-                    "{} crates for vulnerabilities ({} advisories in database)",
-                    lockfile.packages.len(),
-                    advisory_db.iter().len()
-                ),
-                GREEN,
-                true,
-            )
-            .unwrap();
-    }
+    let index = matches.value_of("index");
 
-    let vulnerabilities = lockfile.vulnerabilities(&advisory_db);
-    if let OutputFormat::Text = output_format {
-        if vulnerabilities.is_empty() {
+    if let Some(path) = index {
+        if !Path::new(path).is_dir() {
             shell
-                .say_status("Success", "No vulnerable packages found", GREEN, true)
+                .say_status(
+                    "error:",
+                    format!("no registry index found at {}", path),
+                    RED,
+                    false,
+                )
                 .unwrap();
+            exit(1);
+        }
+    }
+
+    let (advisory_db, stale_db) = load_advisory_dbs(&mut shell, matches, None, &output_format);
+
+    let projects: Vec<_> = lockfile_paths
+        .iter()
+        .map(|path| load_project(&mut shell, matches, path, &advisory_db, &output_format))
+        .collect();
+
+    // Findings from several lockfiles are reported together, so each names its lockfile
+    let multiple = projects.len() > 1;
+    let file = |project: &Project| -> Option<String> {
+        if multiple {
+            Some(project.filename.clone())
         } else {
-            shell
-                .say_status("Warning", "Vulnerable crates found!", RED, true)
-                .unwrap();
+            None
         }
+    };
+
+    // Withdrawn advisories were published in error, so are ignored unless asked for
+    let include_withdrawn = matches.is_present("include-withdrawn");
+    let mut reports = vec![];
+
+    for project in &projects {
+        let (withdrawn, matched): (Vec<_>, Vec<_>) = project
+            .advisory_db
+            .vulnerabilities(&project.lockfile)
+            .into_iter()
+            .partition(|vuln| vuln.advisory.withdrawn.is_some() && !include_withdrawn);
+
+        // Advisories for other platforms are reported, but don't fail the audit
+        let (vulnerabilities, inapplicable): (Vec<_>, Vec<_>) =
+            matched.into_iter().partition(|vuln| {
+                targets
+                    .iter()
+                    .any(|target| vuln.advisory.applies_to(target))
+            });
+
+        // The project's sources are only scanned if an advisory lists affected functions
+        let checks_functions = vulnerabilities
+            .iter()
+            .chain(&inapplicable)
+            .any(|vuln| !vuln.advisory.affected_functions.is_empty());
+
+        let sources = if checks_functions {
+            match SourceIndex::build(&project.dir) {
+                Ok(index) => Some(index),
+                Err(e) => {
+                    if let OutputFormat::Text = output_format {
+                        shell
+                            .say_status(
+                                "warning:",
+                                format!("couldn't scan sources for affected functions: {}", e),
+                                YELLOW,
+                                false,
+                            )
+                            .unwrap();
+                    }
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        reports.push(Report {
+            project: project,
+            vulnerabilities: vulnerabilities,
+            inapplicable: inapplicable,
+            withdrawn: withdrawn,
+            yanked: find_yanked(&project.lockfile, index),
+            sources: sources,
+        });
     }
+
+    let findings: Vec<_> = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .vulnerabilities
+                .iter()
+                .map(move |vuln| (report, vuln))
+        })
+        .collect();
+
+    let inapplicable: Vec<_> = reports
+        .iter()
+        .flat_map(|report| report.inapplicable.iter().map(move |vuln| (report, vuln)))
+        .collect();
+
+    let yanked: Vec<_> = reports
+        .iter()
+        .flat_map(|report| report.yanked.iter().map(move |&package| (report, package)))
+        .collect();
+
+    let reachability = |report: &Report, vuln: &Vulnerability| {
+        report
+            .sources
+            .as_ref()
+            .and_then(|index| index.reachability(vuln.advisory))
+    };
+
+    // Only vulnerabilities fail the audit, unless other findings are denied with `--deny`
+    let denies = |name: &str| {
+        matches
+            .values_of("deny")
+            .map(|mut names| names.any(|n| n == "warnings" || n == name))
+            .unwrap_or(false)
+    };
+
+    let is_denied = |kind: Kind| kind == Kind::Vulnerability || denies(kind.as_str());
+
+    // Findings below `--min-severity` are reported without failing the audit. Those without a
+    // CVSS score can't be ruled out, so they always count.
+    let min_severity = matches.value_of("min-severity").and_then(Severity::parse);
+    let is_failure = |advisory: &Advisory| {
+        is_denied(advisory.kind)
+            && match (min_severity, advisory.cvss.as_ref()) {
+                (Some(min_severity), Some(cvss)) => cvss.severity() >= min_severity,
+                _ => true,
+            }
+    };
+
+    // The record of withdrawn advisories is kept up to date whichever format is used
+    let newly_withdrawn = newly_withdrawn(&reports);
+
+    // The exit status reflects the worst finding in any of the lockfiles, in either format
+    let failed = findings.iter().any(|&(_, vuln)| is_failure(vuln.advisory))
+        || (!yanked.is_empty() && denies("yanked"));
+
     match output_format {
         OutputFormat::Text => {
-            for vuln in &vulnerabilities {
-                display_advisory(&mut shell, vuln.package, vuln.advisory).unwrap();
+            if !findings
+                .iter()
+                .any(|&(_, vuln)| vuln.advisory.kind == Kind::Vulnerability)
+            {
+                shell
+                    .say_status("Success", "No vulnerable packages found", GREEN, true)
+                    .unwrap();
             }
 
-            if !vulnerabilities.is_empty() {
-                vulns_found(&mut shell, vulnerabilities.len()).unwrap();
-                exit(1);
+            // With several lockfiles, findings are grouped by the project they were found in
+            for report in &reports {
+                if multiple && (!report.vulnerabilities.is_empty() || !report.yanked.is_empty()) {
+                    shell.say("", WHITE).unwrap();
+                    shell
+                        .say_status("Project", &report.project.filename, GREEN, true)
+                        .unwrap();
+                }
+
+                // Each kind of advisory is reported in its own section
+                for &kind in Kind::all() {
+                    let section: Vec<_> = report
+                        .vulnerabilities
+                        .iter()
+                        .filter(|vuln| vuln.advisory.kind == kind)
+                        .collect();
+
+                    if section.is_empty() {
+                        continue;
+                    }
+
+                    if kind != Kind::Vulnerability {
+                        shell.say("", WHITE).unwrap();
+                    }
+
+                    let color = if is_denied(kind) { RED } else { YELLOW };
+                    shell
+                        .say_status("Warning", section_heading(kind), color, true)
+                        .unwrap();
+
+                    for vuln in section {
+                        display_advisory(
+                            &mut shell,
+                            vuln,
+                            &report.project.lockfile,
+                            file(report.project).as_ref().map(|f| &f[..]),
+                            reachability(report, vuln),
+                        )
+                        .unwrap();
+                    }
+                }
+
+                if !report.yanked.is_empty() {
+                    let color = if denies("yanked") { RED } else { YELLOW };
+
+                    shell.say("", WHITE).unwrap();
+                    shell
+                        .say_status("Warning", "Yanked crates found!", color, true)
+                        .unwrap();
+
+                    for package in &report.yanked {
+                        shell
+                            .say_status(
+                                "Yanked",
+                                format!("{} {}", package.name, package.version),
+                                color,
+                                true,
+                            )
+                            .unwrap();
+                    }
+                }
+            }
+
+            if !inapplicable.is_empty() {
+                shell.say("", WHITE).unwrap();
+            }
+
+            for &(report, vuln) in &inapplicable {
+                let file = file(report.project);
+                not_applicable(&mut shell, vuln, file.as_ref().map(|f| &f[..]), &targets).unwrap();
+            }
+
+            if !newly_withdrawn.is_empty() {
+                shell
+                    .say_status(
+                        "\nnote:",
+                        "advisories withdrawn since the last run:",
+                        CYAN,
+                        false,
+                    )
+                    .unwrap();
+            }
+
+            for &(report, vuln) in &newly_withdrawn {
+                let mut package = format!("{} {}", vuln.package.name, vuln.package.version);
+
+                if let Some(file) = file(report.project) {
+                    package.push_str(&format!(" in {}", file));
+                }
+
+                shell
+                    .say_status(
+                        "Withdrawn",
+                        format!(
+                            "{} ({}) on {}: {}",
+                            vuln.advisory.id,
+                            package,
+                            vuln.advisory.withdrawn.as_ref().unwrap(),
+                            vuln.advisory.title
+                        ),
+                        CYAN,
+                        true,
+                    )
+                    .unwrap();
+            }
+
+            if !findings.is_empty() || !yanked.is_empty() {
+                shell.say("", WHITE).unwrap();
+            }
+
+            // The summary covers all of the lockfiles
+            for &kind in Kind::all() {
+                let vulns: Vec<_> = findings
+                    .iter()
+                    .map(|&(_, vuln)| vuln)
+                    .filter(|vuln| vuln.advisory.kind == kind)
+                    .collect();
+
+                if vulns.is_empty() {
+                    continue;
+                }
+
+                let failures = vulns
+                    .iter()
+                    .filter(|vuln| is_failure(vuln.advisory))
+                    .count();
+
+                let mut details = severity_counts(&vulns);
+
+                if is_denied(kind) && failures < vulns.len() {
+                    let below = vulns.len() - failures;
+                    details.push_str(&format!("; {} below --min-severity", below));
+                }
+
+                let message = format!("{} found! ({})", kind.count(vulns.len()), details);
+                findings_found(&mut shell, &message, failures > 0).unwrap();
+            }
+
+            if !yanked.is_empty() {
+                let description = if yanked.len() == 1 {
+                    "1 yanked crate".to_owned()
+                } else {
+                    format!("{} yanked crates", yanked.len())
+                };
+
+                let message = format!("{} found!", description);
+                findings_found(&mut shell, &message, denies("yanked")).unwrap();
             }
         }
         OutputFormat::Json => {
-            let vulns: Vec<serde_json::Value> = vulnerabilities
+            let mut vulns: Vec<serde_json::Value> = findings
                 .iter()
-                .map(|vuln| {
+                .map(|&(report, vuln)| (report, vuln, true))
+                .chain(
+                    inapplicable
+                        .iter()
+                        .map(|&(report, vuln)| (report, vuln, false)),
+                )
+                .map(|(report, vuln, applicable)| {
                     let advisory = vuln.advisory;
+                    let reachability = reachability(report, vuln);
+                    let reference = match reachability {
+                        Some(Reachability::Referenced(ref reference)) => Some(json!({
+                            "function": reference.function,
+                            "file": reference.file.display().to_string(),
+                            "line": reference.line,
+                        })),
+                        _ => None,
+                    };
+
                     json!({
                         // tool	"retire"
                         // message	"3rd party CORS request may execute for jquery"
@@ -147,20 +702,797 @@ fn main() {
                         "tool": "cargo-audit",
                         "message": advisory.title,
                         "url": advisory.url,
-                        "cve": advisory.id,
-                        "file": "Cargo.lock",
-                        "priority": "Unknown",
+                        "id": advisory.id,
+                        "cve": advisory.alias("CVE-"),
+                        "ghsa": advisory.alias("GHSA-"),
+                        "aliases": advisory.aliases,
+                        "kind": advisory.kind.as_str(),
+                        "withdrawn": advisory.withdrawn,
+                        "cvss": advisory.cvss.as_ref().map(cvss_json),
+                        "file": report.project.filename,
+                        "source": vuln.source,
+                        "source_commit": vuln.commit,
+                        "affected_versions": advisory
+                            .affected_ranges()
+                            .iter()
+                            .map(|range| range.to_string())
+                            .collect::<Vec<_>>(),
+                        "affected_arch": advisory.affected_arch,
+                        "affected_os": advisory.affected_os,
+                        "applicable": applicable,
+                        "affected_functions": advisory.affected_functions,
+                        "reachability": reachability.as_ref().map(|r| r.label()),
+                        "reference": reference,
+                        "dependency_paths": dependency_paths_json(
+                            &report.project.lockfile,
+                            vuln.package,
+                        ),
+                        "priority": priority(advisory),
                     })
                 })
                 .collect();
+
+            vulns.extend(yanked.iter().map(|&(report, package)| {
+                json!({
+                    "tool": "cargo-audit",
+                    "message": format!("{} {} has been yanked", package.name, package.version),
+                    "url": null,
+                    "id": null,
+                    "cve": null,
+                    "ghsa": null,
+                    "aliases": [],
+                    "kind": "yanked",
+                    "file": report.project.filename,
+                    "package": package.name,
+                    "version": package.version.to_string(),
+                    "priority": "Unknown",
+                })
+            }));
+
+            // Advisories which no longer apply are listed once, so findings which disappear
+            // from one run to the next are explained
+            vulns.extend(newly_withdrawn.iter().map(|&(report, vuln)| {
+                let advisory = vuln.advisory;
+
+                json!({
+                    "tool": "cargo-audit",
+                    "message": advisory.title,
+                    "url": advisory.url,
+                    "id": advisory.id,
+                    "cve": advisory.alias("CVE-"),
+                    "ghsa": advisory.alias("GHSA-"),
+                    "aliases": advisory.aliases,
+                    "kind": "withdrawn",
+                    "withdrawn": advisory.withdrawn,
+                    "file": report.project.filename,
+                    "source": vuln.source,
+                    "source_commit": vuln.commit,
+                    "package": vuln.package.name,
+                    "version": vuln.package.version.to_string(),
+                    "priority": "None",
+                })
+            }));
+
             let json_vulns: serde_json::Value = json!(*vulns);
-            if vulnerabilities.is_empty() {
+            if findings.is_empty() {
                 shell.say(json_vulns, GREEN).unwrap();
             } else {
                 shell.say(json_vulns, RED).unwrap();
             }
         }
     }
+
+    if failed {
+        exit(1);
+    }
+
+    if stale_db && matches.is_present("deny-stale-db") {
+        exit(EXIT_STALE_DATABASE);
+    }
+}
+
+/// Load a lockfile, generating it first if asked to, along with the advisories which apply to
+/// its project: those in the database, and any local advisories in the project's directory
+fn load_project(
+    shell: &mut Shell,
+    matches: &ArgMatches,
+    lockfile_path: &Path,
+    advisory_db: &AdvisoryDatabase,
+    output_format: &OutputFormat,
+) -> Project {
+    let filename = lockfile_path.to_string_lossy().into_owned();
+
+    // The lockfile lives at the root of the project (or workspace)
+    let project_dir = lockfile_path.parent().unwrap_or_else(|| Path::new(""));
+
+    // Library projects often don't commit a lockfile, so cargo can be asked to make one
+    let generated = matches.is_present("generate-lockfile") && !lockfile_path.exists();
+
+    if generated {
+        if let OutputFormat::Text = *output_format {
+            shell
+                .say_status("Generating", &filename, GREEN, true)
+                .unwrap();
+        }
+
+        let manifest = matches.value_of("manifest-path").map(Path::new);
+
+        if let Err(e) = workspace::generate_lockfile(project_dir, manifest) {
+            shell.say_status("error:", e, RED, false).unwrap();
+            exit(1);
+        }
+    }
+
+    let lockfile = match Lockfile::load(lockfile_path) {
+        Ok(lf) => lf,
+        Err(Error::IO(_)) => {
+            not_found(shell, &filename).unwrap();
+            exit(1);
+        }
+        Err(e) => {
+            shell.say_status("error:", e, RED, false).unwrap();
+            exit(1);
+        }
+    };
+
+    // Once loaded the generated lockfile isn't needed, so it's removed straight away rather
+    // than on each of the ways the audit can exit
+    if generated && !matches.is_present("keep-lockfile") {
+        if let Err(e) = fs::remove_file(lockfile_path) {
+            shell
+                .say_status(
+                    "warning:",
+                    format!("couldn't remove {}: {}", filename, e),
+                    YELLOW,
+                    false,
+                )
+                .unwrap();
+        }
+    }
+
+    let mut project_db = advisory_db.clone();
+
+    if let Some(overlay) = load_overlay(shell, project_dir, output_format) {
+        project_db.merge(overlay);
+    }
+
+    if let OutputFormat::Text = *output_format {
+        shell
+            .say_status(
+                "Scanning",
+                &format!(
+                    "{} crates in {} for vulnerabilities ({} advisories in database)",
+                    lockfile.packages.len(),
+                    filename,
+                    project_db.len()
+                ),
+                GREEN,
+                true,
+            )
+            .unwrap();
+    }
+
+    Project {
+        filename: filename,
+        dir: project_dir.to_owned(),
+        lockfile: lockfile,
+        advisory_db: project_db,
+    }
+}
+
+/// Look up advisories in the database without auditing a project (`cargo audit db`)
+fn browse_db(command: &str, matches: &ArgMatches) {
+    let (mut shell, output_format) = output_options(matches);
+    let (advisory_db, stale_db) =
+        load_advisory_dbs(&mut shell, matches, Some(Path::new("")), &output_format);
+
+    match command {
+        "list" => {
+            let advisories: Vec<_> = advisory_db.iter().collect();
+            browse::list(&mut shell, &advisory_db, &advisories, &output_format).unwrap();
+        }
+        "show" => {
+            let id = matches.value_of("ID").unwrap();
+
+            match advisory_db.get(id) {
+                Some(advisory) => {
+                    browse::show(&mut shell, &advisory_db, advisory, &output_format).unwrap()
+                }
+                None => {
+                    shell
+                        .say_status("error:", format!("No advisory `{}` found", id), RED, false)
+                        .unwrap();
+                    exit(1);
+                }
+            }
+        }
+        "search" => {
+            let query = matches.value_of("QUERY").unwrap();
+            let advisories = advisory_db.search(query);
+
+            if advisories.is_empty() {
+                if let OutputFormat::Text = output_format {
+                    shell
+                        .say_status(
+                            "Success",
+                            format!("No advisories matching `{}`", query),
+                            GREEN,
+                            true,
+                        )
+                        .unwrap();
+                }
+            }
+
+            browse::list(&mut shell, &advisory_db, &advisories, &output_format).unwrap();
+        }
+        _ => unreachable!(),
+    }
+
+    if stale_db && matches.is_present("deny-stale-db") {
+        exit(EXIT_STALE_DATABASE);
+    }
+}
+
+/// Check advisory files for mistakes (`cargo audit db lint`)
+fn lint_db(matches: &ArgMatches) {
+    let (mut shell, output_format) = output_options(matches);
+    let path = matches.value_of("PATH").unwrap();
+
+    let report = match lint::lint(Path::new(path)) {
+        Ok(report) => report,
+        Err(e) => {
+            shell.say_status("error:", e, RED, false).unwrap();
+            exit(1);
+        }
+    };
+
+    browse::lint_report(&mut shell, &report, &output_format).unwrap();
+
+    if report.count(lint::Severity::Error) > 0 {
+        exit(1);
+    }
+}
+
+/// Create the shell and determine the output format from the `--color` and `--format` options
+fn output_options(matches: &ArgMatches) -> (Shell, OutputFormat) {
+    let shell = shell::create(match matches.value_of("color").unwrap_or("auto") {
+        "always" => ColorConfig::Always,
+        "never" => ColorConfig::Never,
+        _ => ColorConfig::Auto,
+    });
+
+    let output_format = match matches.value_of("format").unwrap_or("text") {
+        "text" => OutputFormat::Text,
+        "json" => OutputFormat::Json,
+        _ => OutputFormat::Text,
+    };
+
+    (shell, output_format)
+}
+
+/// Load and merge the advisory databases selected by the command-line options, along with any
+/// project-specific advisories in `project_dir`. Also returns whether any of the databases is
+/// older than `--max-db-age`.
+fn load_advisory_dbs(
+    shell: &mut Shell,
+    matches: &ArgMatches,
+    project_dir: Option<&Path>,
+    output_format: &OutputFormat,
+) -> (AdvisoryDatabase, bool) {
+    let db_options = DatabaseOptions {
+        no_fetch: matches.is_present("no-fetch"),
+        git_rev: matches.value_of("db-rev"),
+        integrity: Integrity {
+            sha256: matches
+                .values_of("db-sha256")
+                .map(|digests| digests.collect())
+                .unwrap_or_else(Vec::new),
+            keyring: matches.value_of("keyring").map(Path::new),
+            signature: matches.value_of("db-signature"),
+        },
+    };
+
+    let max_db_age = matches
+        .value_of("max-db-age")
+        .map(|days| days.parse::<u64>().unwrap());
+
+    // Local databases take precedence over fetched ones when advisory IDs collide
+    let mut sources: Vec<Source> = matches
+        .values_of("db")
+        .map(|paths| paths.map(Source::Path).collect())
+        .unwrap_or_else(Vec::new);
+
+    let git_urls: Vec<_> = matches
+        .values_of("git-url")
+        .map(|urls| urls.collect())
+        .unwrap_or_else(Vec::new);
+
+    // Commits belong to a single repository
+    if db_options.git_rev.is_some() && git_urls.len() > 1 {
+        shell
+            .say_status(
+                "error:",
+                "--db-rev can't be used with more than one --git-url",
+                RED,
+                false,
+            )
+            .unwrap();
+        exit(1);
+    }
+
+    // Each repository is cloned into its own directory in the cache
+    sources.extend(git_urls.into_iter().map(Source::Git));
+
+    for url in matches.values_of("url").into_iter().flat_map(|urls| urls) {
+        // `file://` URLs are equivalent to passing a local path with `--db`
+        if url.starts_with("file://") {
+            sources.push(Source::Path(&url["file://".len()..]));
+        } else {
+            sources.push(Source::Url(url));
+        }
+    }
+
+    if sources.is_empty() {
+        sources.push(Source::Url(ADVISORY_DB_URL));
+    }
+
+    let mut fetcher = LazyFetcher {
+        matches: matches,
+        fetcher: None,
+    };
+
+    let cache = Cache::default_dir().map(Cache::new);
+    let mut advisory_db = AdvisoryDatabase::default();
+    let mut duplicates = 0;
+
+    if let Some(project_dir) = project_dir {
+        if let Some(db) = load_overlay(shell, project_dir, output_format) {
+            advisory_db.merge(db);
+        }
+    }
+
+    let mut stale_db = false;
+
+    for source in &sources {
+        let (db, synced_at) = load_advisory_db(
+            shell,
+            source,
+            cache.as_ref(),
+            &db_options,
+            &mut fetcher,
+            output_format,
+        );
+
+        if let Some(max_age) = max_db_age {
+            // Only git commits record when upstream last changed: a successful fetch from a URL
+            // (which may be a stale mirror) or a local path says nothing, so fall back to the
+            // newest advisory
+            if let Some(updated) = synced_at.or_else(|| db.newest_advisory_date()) {
+                let age = date::days_since(updated);
+
+                if age > max_age {
+                    stale_db = true;
+
+                    if let OutputFormat::Text = *output_format {
+                        shell
+                            .say_status(
+                                "Warning",
+                                &format!(
+                                    "advisory database `{}` was last updated {} days ago \
+                                     (maximum age: {} days)",
+                                    source.name(),
+                                    age,
+                                    max_age
+                                ),
+                                RED,
+                                true,
+                            )
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        duplicates += advisory_db.merge(db);
+    }
+
+    if duplicates > 0 {
+        if let OutputFormat::Text = *output_format {
+            shell
+                .say_status(
+                    "Merged",
+                    &format!(
+                        "{} advisory databases ({} duplicate advisories ignored)",
+                        sources.len(),
+                        duplicates
+                    ),
+                    GREEN,
+                    true,
+                )
+                .unwrap();
+        }
+    }
+
+    (advisory_db, stale_db)
+}
+
+/// Load the project-specific advisories in `project_dir`, if it has any
+fn load_overlay(
+    shell: &mut Shell,
+    project_dir: &Path,
+    output_format: &OutputFormat,
+) -> Option<AdvisoryDatabase> {
+    let overlay = project_dir.join(OVERLAY_PATH);
+
+    if !overlay.is_file() {
+        return None;
+    }
+
+    let name = overlay.display().to_string();
+
+    if let OutputFormat::Text = *output_format {
+        shell
+            .say_status(
+                "Loading",
+                &format!("local advisories `{}`", name),
+                GREEN,
+                true,
+            )
+            .unwrap();
+    }
+
+    match AdvisoryDatabase::load_overlay(&overlay, &name) {
+        Ok(db) => Some(db),
+        Err(e) => {
+            shell.say_status("error:", e, RED, false).unwrap();
+            exit(1);
+        }
+    }
+}
+
+impl<'a> LazyFetcher<'a> {
+    /// The client to fetch with, setting it up from the command line and cargo's configuration
+    /// on first use. Exits if the network settings are invalid.
+    fn get(&mut self, shell: &mut Shell, output_format: &OutputFormat) -> &Fetcher {
+        if self.fetcher.is_none() {
+            let config = Config::load();
+
+            if let OutputFormat::Text = *output_format {
+                for e in config.ignored() {
+                    shell
+                        .say_status(
+                            "Warning",
+                            &format!("ignoring cargo configuration ({})", e),
+                            RED,
+                            true,
+                        )
+                        .unwrap();
+                }
+            }
+
+            match new_fetcher(self.matches, &config) {
+                Ok(fetcher) => self.fetcher = Some(fetcher),
+                Err(e) => {
+                    shell.say_status("error:", e, RED, false).unwrap();
+                    exit(1);
+                }
+            }
+        }
+
+        self.fetcher.as_ref().unwrap()
+    }
+}
+
+/// Create a client using the network settings from the command line, falling back to cargo's
+/// own `[http]` settings for anything not given there
+fn new_fetcher(matches: &ArgMatches, config: &Config) -> error::Result<Fetcher> {
+    let timeout = match matches.value_of("fetch-timeout") {
+        Some(secs) => Some(secs.parse().unwrap()),
+        None => config.get_u64("http.timeout")?,
+    };
+
+    let retries = match matches.value_of("fetch-retries") {
+        Some(n) => Some(n.parse().unwrap()),
+        None => config.get_u64("net.retry")?,
+    };
+
+    Fetcher::new(&FetchOptions {
+        proxy: matches
+            .value_of("proxy")
+            .map(|proxy| proxy.to_owned())
+            .or_else(|| config.get_str("http.proxy")),
+        ca_cert: matches
+            .value_of("ca-cert")
+            .map(PathBuf::from)
+            .or_else(|| config.get_path("http.cainfo")),
+        timeout: timeout,
+        retries: retries,
+    })
+}
+
+/// Load the advisory database from the given source, along with the time (in seconds since the
+/// Unix epoch) of the upstream commit it was synchronized to, if known
+fn load_advisory_db(
+    shell: &mut Shell,
+    source: &Source,
+    cache: Option<&Cache>,
+    options: &DatabaseOptions,
+    fetcher: &mut LazyFetcher,
+    output_format: &OutputFormat,
+) -> (AdvisoryDatabase, Option<u64>) {
+    let mut synced_at = None;
+
+    let result = match *source {
+        Source::Path(path) => {
+            if let OutputFormat::Text = *output_format {
+                shell
+                    .say_status("Loading", &format!("advisories `{}`", path), GREEN, true)
+                    .unwrap();
+            }
+
+            if options.integrity.is_enabled() {
+                if Path::new(path).is_dir() {
+                    integrity_unsupported(shell, path);
+                }
+
+                files::read(Path::new(path)).and_then(|data| {
+                    let signature = options.integrity.fetch_signature(path, |url| {
+                        fetcher.get(shell, output_format).fetch(url)
+                    })?;
+                    options
+                        .integrity
+                        .check(&data, signature.as_ref().map(|s| &s[..]))?;
+                    AdvisoryDatabase::from_toml(&into_string(data, path)?, source.name())
+                })
+            } else {
+                AdvisoryDatabase::load(Path::new(path), source.name())
+            }
+        }
+        Source::Git(url) => {
+            if options.integrity.is_enabled() {
+                integrity_unsupported(shell, url);
+            }
+
+            let repo = match cache {
+                Some(cache) => Repository::new(cache.repository_dir(url)),
+                None => {
+                    no_cached_db(shell, None, url).unwrap();
+                    exit(1);
+                }
+            };
+
+            let commit = if options.no_fetch {
+                repo.open(options.git_rev)
+            } else {
+                if let OutputFormat::Text = *output_format {
+                    shell
+                        .say_status("Fetching", &format!("advisories `{}`", url), GREEN, true)
+                        .unwrap();
+                }
+
+                repo.sync(url, options.git_rev)
+            };
+
+            match commit {
+                Ok(commit) => {
+                    if let OutputFormat::Text = *output_format {
+                        shell
+                            .say_status(
+                                "Using",
+                                &format!(
+                                    "advisory-db commit {} ({})",
+                                    commit.short_id(),
+                                    commit.summary
+                                ),
+                                GREEN,
+                                true,
+                            )
+                            .unwrap();
+                    }
+
+                    synced_at = Some(commit.timestamp);
+                    AdvisoryDatabase::load(repo.path(), source.name()).map(|mut db| {
+                        db.set_commit(source.name(), &commit.id);
+                        db
+                    })
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Source::Url(url) => {
+            let fetched = if options.no_fetch {
+                None
+            } else {
+                if let OutputFormat::Text = *output_format {
+                    shell
+                        .say_status("Fetching", &format!("advisories `{}`", url), GREEN, true)
+                        .unwrap();
+                }
+
+                match fetcher.get(shell, output_format).fetch(url) {
+                    Ok(data) => Some(data),
+                    // Fall back to the last database which was fetched successfully, if any
+                    Err(e) => {
+                        if cache.map(|c| c.path(url).exists()).unwrap_or(false) {
+                            if let OutputFormat::Text = *output_format {
+                                shell
+                                    .say_status(
+                                        "Warning",
+                                        &format!(
+                                            "couldn't fetch advisories ({}), using cached copy",
+                                            e
+                                        ),
+                                        RED,
+                                        true,
+                                    )
+                                    .unwrap();
+                            }
+
+                            None
+                        } else {
+                            fetch_failed(shell, &e).unwrap();
+                            exit(EXIT_FETCH_FAILED);
+                        }
+                    }
+                }
+            };
+
+            if let Some(data) = fetched {
+                // Never cache (or use) a database which fails verification
+                let verified = options
+                    .integrity
+                    .fetch_signature(url, |url| fetcher.get(shell, output_format).fetch(url))
+                    .and_then(|signature| {
+                        options
+                            .integrity
+                            .check(&data, signature.as_ref().map(|s| &s[..]))?;
+                        Ok(signature)
+                    });
+
+                let (toml, signature) =
+                    match verified.and_then(|signature| Ok((into_string(data, url)?, signature))) {
+                        Ok(verified) => verified,
+                        Err(e) => {
+                            shell.say_status("error:", e, RED, false).unwrap();
+                            exit(1);
+                        }
+                    };
+
+                let result = AdvisoryDatabase::from_toml(&toml, source.name());
+
+                if result.is_ok() {
+                    // A cache we can't write to shouldn't prevent the audit itself
+                    let stored = cache.map(|c| {
+                        c.store(url, &toml)?;
+
+                        match signature {
+                            Some(ref signature) => c.store_signature(url, signature),
+                            None => Ok(()),
+                        }
+                    });
+
+                    if let Some(Err(e)) = stored {
+                        if let OutputFormat::Text = *output_format {
+                            shell
+                                .say_status(
+                                    "Warning",
+                                    &format!("couldn't cache advisories ({})", e),
+                                    RED,
+                                    true,
+                                )
+                                .unwrap();
+                        }
+                    }
+                }
+
+                result
+            } else {
+                let cached = match cache.map(|c| c.load(url)) {
+                    Some(Ok(Some(cached))) => cached,
+                    Some(Ok(None)) | None => {
+                        no_cached_db(shell, cache, url).unwrap();
+                        exit(1);
+                    }
+                    Some(Err(e)) => {
+                        shell.say_status("error:", e, RED, false).unwrap();
+                        exit(1);
+                    }
+                };
+
+                if let OutputFormat::Text = *output_format {
+                    shell
+                        .say_status(
+                            "Loading",
+                            &format!(
+                                "cached advisories (fetched {} ago)",
+                                cache::describe_age(&cached.age)
+                            ),
+                            GREEN,
+                            true,
+                        )
+                        .unwrap();
+                }
+
+                // The cache is only as trustworthy as the filesystem it lives on
+                let signature = match options.integrity.signature {
+                    Some(_) => options
+                        .integrity
+                        .fetch_signature(url, |url| fetcher.get(shell, output_format).fetch(url)),
+                    None => cache.unwrap().load_signature(url),
+                };
+
+                signature
+                    .and_then(|signature| {
+                        options
+                            .integrity
+                            .check(cached.toml.as_bytes(), signature.as_ref().map(|s| &s[..]))
+                    })
+                    .and_then(|()| AdvisoryDatabase::from_toml(&cached.toml, source.name()))
+            }
+        }
+    };
+
+    // An empty database would pass every audit, e.g. when pointed at the wrong directory
+    let result = result.and_then(|db| {
+        if db.len() == 0 {
+            Err(Error::Parse(format!(
+                "no advisories found in `{}`",
+                source.name()
+            )))
+        } else {
+            Ok(db)
+        }
+    });
+
+    match result {
+        Ok(db) => (db, synced_at),
+        Err(e) => {
+            shell.say_status("error:", e, RED, false).unwrap();
+            exit(1);
+        }
+    }
+}
+
+fn integrity_unsupported(shell: &mut Shell, location: &str) -> ! {
+    shell
+        .say_status(
+            "error:",
+            format!(
+                "Integrity checks are only supported for single-file databases, not `{}`",
+                location
+            ),
+            RED,
+            false,
+        )
+        .unwrap();
+    exit(1);
+}
+
+fn is_number(value: String) -> Result<(), String> {
+    value
+        .parse::<u64>()
+        .map(|_| ())
+        .map_err(|_| "must be a non-negative integer".to_owned())
+}
+
+fn into_string(data: Vec<u8>, location: &str) -> error::Result<String> {
+    String::from_utf8(data).map_err(|e| Error::Parse(format!("{}: {}", location, e)))
+}
+
+fn fetch_failed(shell: &mut Shell, error: &Error) -> term::Result<()> {
+    shell.say_status(
+        "error:",
+        format!("Couldn't fetch advisory database: {}", error),
+        RED,
+        false,
+    )?;
+    shell.say(
+        "\nCheck your network connection and proxy settings, or retry with a longer \
+         --fetch-timeout",
+        WHITE,
+    )?;
+
+    Ok(())
 }
 
 fn not_found(shell: &mut Shell, filename: &str) -> term::Result<()> {
@@ -171,43 +1503,264 @@ fn not_found(shell: &mut Shell, filename: &str) -> term::Result<()> {
         false,
     )?;
     shell.say(
-        "\nRun \"cargo build\" to generate lockfile before running audit",
+        "\nRun \"cargo build\" to generate lockfile before running audit, or retry with \
+         --generate-lockfile",
         WHITE,
     )?;
 
     Ok(())
 }
 
-fn vulns_found(shell: &mut Shell, vuln_count: usize) -> term::Result<()> {
-    if vuln_count == 1 {
-        shell.say_status("\nerror:", "1 vulnerability found!", RED, false)?;
-    } else {
-        shell.say_status(
-            "\nerror:",
-            format!("{} vulnerabilities found!", vuln_count),
+fn no_cached_db(shell: &mut Shell, cache: Option<&Cache>, url: &str) -> term::Result<()> {
+    match cache {
+        Some(cache) => shell.say_status(
+            "error:",
+            format!(
+                "No cached advisory database at '{}'!",
+                cache.path(url).display()
+            ),
             RED,
             false,
-        )?;
+        )?,
+        None => shell.say_status(
+            "error:",
+            "Couldn't determine the advisory database cache directory!",
+            RED,
+            false,
+        )?,
     }
 
+    shell.say(
+        "\nRun \"cargo audit\" without --no-fetch to fetch the advisory database",
+        WHITE,
+    )?;
+
     Ok(())
 }
 
-fn display_advisory(shell: &mut Shell, package: &Package, advisory: &Advisory) -> term::Result<()> {
+/// Withdrawn advisories matching each lockfile which hadn't been withdrawn when it was previously
+/// audited against the same database. The advisories withdrawn now are recorded for the next run
+/// to compare against; nothing is reported for lockfiles and databases without a previous record.
+fn newly_withdrawn<'a, 'b>(
+    reports: &'b [Report<'a>],
+) -> Vec<(&'b Report<'a>, &'b Vulnerability<'a>)> {
+    let cache = match Cache::default_dir() {
+        Some(dir) => Cache::new(dir),
+        None => return vec![],
+    };
+
+    let mut newly_withdrawn = vec![];
+
+    for report in reports {
+        let db = &report.project.advisory_db;
+        let lockfile = lockfile_id(report.project);
+
+        // Every source gets a record, so the first advisory withdrawn from it is noticed
+        let mut current: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+
+        for advisory in db.iter() {
+            let ids = current.entry(db.source(advisory)).or_insert_with(Vec::new);
+
+            if advisory.withdrawn.is_some() {
+                ids.push(&advisory.id);
+            }
+        }
+
+        for (source, ids) in current {
+            // This only affects an informational notice, so problems with the cache aren't fatal
+            let previous = cache.load_withdrawn(&lockfile, source).unwrap_or(None);
+
+            if previous
+                .as_ref()
+                .map(|previous| previous != &ids)
+                .unwrap_or(true)
+            {
+                cache.store_withdrawn(&lockfile, source, &ids).ok();
+            }
+
+            if let Some(previous) = previous {
+                newly_withdrawn.extend(
+                    report
+                        .withdrawn
+                        .iter()
+                        .filter(|vuln| {
+                            vuln.source == source && !previous.contains(&vuln.advisory.id)
+                        })
+                        .map(|vuln| (report, vuln)),
+                );
+            }
+        }
+    }
+
+    newly_withdrawn
+}
+
+/// Absolute path of a project's lockfile, which identifies it between runs
+fn lockfile_id(project: &Project) -> PathBuf {
+    let dir = env::current_dir()
+        .map(|cwd| cwd.join(&project.dir))
+        .unwrap_or_else(|_| project.dir.clone());
+    let name = Path::new(&project.filename)
+        .file_name()
+        .unwrap_or_else(|| OsStr::new("Cargo.lock"));
+
+    fs::canonicalize(&dir).unwrap_or(dir).join(name)
+}
+
+/// Find registry packages in the lockfile whose locked versions have since been yanked, using
+/// cargo's local copies of each registry's index. `index` replaces cargo's copy of crates.io's.
+fn find_yanked<'a>(lockfile: &'a Lockfile, index: Option<&str>) -> Vec<&'a Package> {
+    let mut indexes: HashMap<&str, Vec<RegistryIndex>> = HashMap::new();
+
+    lockfile
+        .packages
+        .iter()
+        .filter(|package| {
+            let source = match package.source {
+                Some(ref source) if package.is_registry() => source,
+                _ => return false,
+            };
+
+            // An explicitly given index is a copy of crates.io's, so other registries are
+            // still looked up in cargo's own copies
+            let indexes = indexes.entry(source).or_insert_with(|| match index {
+                Some(path) if registry::is_crates_io(source) => vec![RegistryIndex::new(path)],
+                _ => RegistryIndex::find_local(source),
+            });
+
+            indexes
+                .iter()
+                .filter_map(|index| index.is_yanked(&package.name, &package.version))
+                .next()
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Heading for the section of the output listing advisories of the given kind
+fn section_heading(kind: Kind) -> &'static str {
+    match kind {
+        Kind::Vulnerability => "Vulnerable crates found!",
+        Kind::Unmaintained => "Unmaintained crates found!",
+        Kind::Unsound => "Unsound crates found!",
+        Kind::Notice => "Crates with notices found!",
+    }
+}
+
+/// Breakdown of findings by severity, e.g. `1 critical, 2 unknown`
+fn severity_counts(findings: &[&Vulnerability]) -> String {
+    let severities = [
+        Some(Severity::Critical),
+        Some(Severity::High),
+        Some(Severity::Medium),
+        Some(Severity::Low),
+        Some(Severity::None),
+        None,
+    ];
+
+    let counts: Vec<_> = severities
+        .iter()
+        .filter_map(|&severity| {
+            let count = findings
+                .iter()
+                .filter(|vuln| vuln.advisory.cvss.as_ref().map(|cvss| cvss.severity()) == severity)
+                .count();
+
+            let name = severity
+                .map(|severity| severity.as_str())
+                .unwrap_or("unknown");
+
+            if count > 0 {
+                Some(format!("{} {}", count, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    counts.join(", ")
+}
+
+fn findings_found(shell: &mut Shell, message: &str, denied: bool) -> term::Result<()> {
+    let (status, color) = if denied {
+        ("error:", RED)
+    } else {
+        ("warning:", YELLOW)
+    };
+
+    shell.say_status(status, message, color, false)
+}
+
+fn display_advisory(
+    shell: &mut Shell,
+    vuln: &Vulnerability,
+    lockfile: &Lockfile,
+    file: Option<&str>,
+    reachability: Option<Reachability>,
+) -> term::Result<()> {
+    let (package, advisory) = (vuln.package, vuln.advisory);
+
+    // Every attribute of the advisory is colored by its severity
+    let color = severity_color(advisory);
+    let attribute =
+        |shell: &mut Shell, name: &str, value: &str| colored_attribute(shell, name, value, color);
+
     attribute(shell, "\nID", &advisory.id)?;
+
+    if !advisory.aliases.is_empty() {
+        attribute(shell, "Aliases", &advisory.aliases.join(", "))?;
+    }
     attribute(shell, "Crate", &package.name)?;
     attribute(shell, "Version", &package.version.to_string())?;
 
+    if let Some(file) = file {
+        attribute(shell, "Lockfile", file)?;
+    }
+
     if let Some(ref date) = advisory.date {
         attribute(shell, "Date", date)?;
     }
 
+    if let Some(ref withdrawn) = advisory.withdrawn {
+        attribute(shell, "Withdrawn", withdrawn)?;
+    }
+
     if let Some(ref url) = advisory.url {
         attribute(shell, "URL", url)?;
     }
 
     attribute(shell, "Title", &advisory.title)?;
 
+    if let Some(ref cvss) = advisory.cvss {
+        attribute(shell, "Severity", &severity(cvss))?;
+    }
+
+    attribute(shell, "Source", vuln.source)?;
+    attribute(shell, "Affected versions", &affected_versions(advisory))?;
+
+    if let Some(platforms) = advisory.platforms() {
+        attribute(shell, "Platforms", &platforms)?;
+    }
+
+    if !advisory.affected_functions.is_empty() {
+        attribute(shell, "Functions", &advisory.affected_functions.join(", "))?;
+    }
+
+    match reachability {
+        Some(Reachability::Referenced(reference)) => attribute(
+            shell,
+            "Reachability",
+            &format!(
+                "referenced (`{}` at {}:{})",
+                reference.function,
+                reference.file.display(),
+                reference.line
+            ),
+        )?,
+        Some(Reachability::NotReferenced) => attribute(shell, "Reachability", "not referenced")?,
+        None => (),
+    }
+
     let mut fixed_versions = String::new();
     let version_count = advisory.patched_versions.len();
 
@@ -219,11 +1772,160 @@ fn display_advisory(shell: &mut Shell, package: &Package, advisory: &Advisory) -
         }
     }
 
-    attribute(shell, "Solution: upgrade to", &fixed_versions)?;
+    if fixed_versions.is_empty() {
+        attribute(shell, "Solution", "no patched versions available")?;
+    } else {
+        attribute(shell, "Solution: upgrade to", &fixed_versions)?;
+    }
+
+    // An inverse tree of the crates which pull this one in, unless it's a root crate itself
+    let (paths, truncated) = lockfile.dependency_paths(package, MAX_DEPENDENCY_PATHS);
+
+    if paths.iter().any(|path| path.len() > 1) {
+        attribute(shell, "Dependency tree", "")?;
+        shell.say(format!("{} {}", package.name, package.version), color)?;
+
+        let paths: Vec<_> = paths.iter().map(|path| &path[..]).collect();
+        let mut lines = vec![];
+        dependency_tree(&paths, "", &mut lines);
+
+        for line in lines {
+            shell.say(line, color)?;
+        }
+
+        if truncated {
+            shell.say(
+                format!("(only the first {} paths are shown)", MAX_DEPENDENCY_PATHS),
+                color,
+            )?;
+        }
+    }
 
     Ok(())
 }
 
-fn attribute(shell: &mut Shell, name: &str, value: &str) -> term::Result<()> {
-    shell.say_status(format!("{}:", name), value, RED, false)
+/// Lines of a tree drawing the given paths, which all start with the same package, below that
+/// package. Paths sharing the same next package are drawn as a single branch.
+fn dependency_tree(paths: &[&[&Package]], prefix: &str, lines: &mut Vec<String>) {
+    let mut branches: Vec<(&Package, Vec<&[&Package]>)> = vec![];
+
+    for path in paths {
+        if path.len() < 2 {
+            continue;
+        }
+
+        match branches
+            .iter()
+            .position(|&(package, _)| ptr::eq(package, path[1]))
+        {
+            Some(i) => branches[i].1.push(&path[1..]),
+            None => branches.push((path[1], vec![&path[1..]])),
+        }
+    }
+
+    for (i, &(package, ref paths)) in branches.iter().enumerate() {
+        let (branch, indent) = if i == branches.len() - 1 {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        lines.push(format!(
+            "{}{}{} {}",
+            prefix, branch, package.name, package.version
+        ));
+        dependency_tree(paths, &format!("{}{}", prefix, indent), lines);
+    }
+}
+
+/// Note an advisory which matched a package, but can't affect any of the target platforms
+fn not_applicable(
+    shell: &mut Shell,
+    vuln: &Vulnerability,
+    file: Option<&str>,
+    targets: &[Target],
+) -> term::Result<()> {
+    let targets: Vec<_> = targets.iter().map(|target| target.to_string()).collect();
+    let mut package = format!("{} {}", vuln.package.name, vuln.package.version);
+
+    if let Some(file) = file {
+        package.push_str(&format!(" in {}", file));
+    }
+
+    shell.say_status(
+        "warning:",
+        format!(
+            "{} ({}) only affects {}, not {}",
+            vuln.advisory.id,
+            package,
+            vuln.advisory.platforms().unwrap_or_default(),
+            targets.join(", ")
+        ),
+        YELLOW,
+        false,
+    )
+}
+
+/// Paths from a package to the root crates depending on it, as arrays of `name version`
+fn dependency_paths_json(lockfile: &Lockfile, package: &Package) -> serde_json::Value {
+    let (paths, _) = lockfile.dependency_paths(package, MAX_DEPENDENCY_PATHS);
+
+    json!(paths
+        .iter()
+        .map(|path| {
+            path.iter()
+                .map(|package| format!("{} {}", package.name, package.version))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dependency_tree;
+    use lockfile::Lockfile;
+
+    #[test]
+    fn dependency_tree_branches() {
+        let lockfile =
+            Lockfile::from_toml(include_str!("../tests/support/dependents.lock")).unwrap();
+        let vuln = lockfile
+            .packages
+            .iter()
+            .find(|package| package.name == "vuln")
+            .unwrap();
+
+        let (paths, _) = lockfile.dependency_paths(vuln, 10);
+        let paths: Vec<_> = paths.iter().map(|path| &path[..]).collect();
+        let mut lines = vec![];
+        dependency_tree(&paths, "", &mut lines);
+
+        assert_eq!(
+            lines,
+            vec![
+                "├── b 1.0.0",
+                "│   ├── app 0.1.0",
+                "│   └── a 0.1.0",
+                "│       └── app 0.1.0",
+                "├── b 2.0.0",
+                "│   └── a 0.1.0",
+                "│       └── app 0.1.0",
+                "└── tests-helper 0.1.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn dependency_tree_root_crate() {
+        let lockfile =
+            Lockfile::from_toml(include_str!("../tests/support/dependents.lock")).unwrap();
+        let app = &lockfile.packages[0];
+
+        let (paths, _) = lockfile.dependency_paths(app, 10);
+        let paths: Vec<_> = paths.iter().map(|path| &path[..]).collect();
+        let mut lines = vec![];
+        dependency_tree(&paths, "", &mut lines);
+
+        assert!(lines.is_empty());
+    }
 }
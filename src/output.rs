@@ -0,0 +1,84 @@
+//! Formatting shared by audit reports and the `cargo audit db` subcommands
+
+use advisory::Advisory;
+use cvss::{Cvss, Severity};
+use serde_json;
+use shell::Shell;
+use term;
+use term::color::{Color, CYAN, RED, YELLOW};
+
+/// Format in which results are printed
+#[derive(Debug)]
+pub enum OutputFormat {
+    /// Human-readable, colored text
+    Text,
+
+    /// JSON array of findings
+    Json,
+}
+
+/// Human-readable description of the versions affected by an advisory
+pub fn affected_versions(advisory: &Advisory) -> String {
+    let ranges: Vec<_> = advisory
+        .affected_ranges()
+        .iter()
+        .map(|range| range.to_string())
+        .collect();
+
+    if ranges.is_empty() {
+        "none".to_owned()
+    } else {
+        ranges.join(" || ")
+    }
+}
+
+/// Human-readable description of a CVSS score, e.g. `9.8 (critical)`
+pub fn severity(cvss: &Cvss) -> String {
+    format!("{:.1} ({})", cvss.score(), cvss.severity())
+}
+
+/// Color for displaying an advisory, by severity. Advisories without a CVSS score are treated
+/// as severe.
+pub fn severity_color(advisory: &Advisory) -> Color {
+    match advisory.cvss.as_ref().map(|cvss| cvss.severity()) {
+        Some(Severity::None) | Some(Severity::Low) => CYAN,
+        Some(Severity::Medium) => YELLOW,
+        Some(Severity::High) | Some(Severity::Critical) | None => RED,
+    }
+}
+
+/// Priority of an advisory in JSON output, e.g. `High`
+pub fn priority(advisory: &Advisory) -> &'static str {
+    match advisory.cvss.as_ref().map(|cvss| cvss.severity()) {
+        Some(Severity::None) => "None",
+        Some(Severity::Low) => "Low",
+        Some(Severity::Medium) => "Medium",
+        Some(Severity::High) => "High",
+        Some(Severity::Critical) => "Critical",
+        None => "Unknown",
+    }
+}
+
+/// JSON representation of a CVSS score
+pub fn cvss_json(cvss: &Cvss) -> serde_json::Value {
+    json!({
+        "vector": cvss.vector(),
+        "score": cvss.score(),
+        "severity": cvss.severity().as_str(),
+    })
+}
+
+/// Print a labelled attribute of an advisory
+pub fn attribute(shell: &mut Shell, name: &str, value: &str) -> term::Result<()> {
+    colored_attribute(shell, name, value, RED)
+}
+
+/// Print a labelled attribute of an advisory in the given color
+pub fn colored_attribute(
+    shell: &mut Shell,
+    name: &str,
+    value: &str,
+    color: Color,
+) -> term::Result<()> {
+    shell.say_status(format!("{}:", name), value, color, false)
+}
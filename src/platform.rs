@@ -0,0 +1,114 @@
+//! Target platforms, for advisories which only affect some architectures or operating systems
+//!
+//! Architecture and OS names are those used by Rust's `target_arch` and `target_os`
+//! configuration options, e.g. `x86_64` and `windows`.
+
+use std::env::consts;
+use std::fmt;
+
+/// Architectures which may appear in an advisory's `affected_arch`
+pub const ARCHES: &'static [&'static str] = &[
+    "aarch64",
+    "arm",
+    "mips",
+    "mips64",
+    "powerpc",
+    "powerpc64",
+    "s390x",
+    "sparc64",
+    "wasm32",
+    "x86",
+    "x86_64",
+];
+
+/// Operating systems which may appear in an advisory's `affected_os`
+pub const OSES: &'static [&'static str] = &[
+    "android",
+    "bitrig",
+    "dragonfly",
+    "emscripten",
+    "freebsd",
+    "fuchsia",
+    "haiku",
+    "ios",
+    "linux",
+    "macos",
+    "netbsd",
+    "openbsd",
+    "redox",
+    "solaris",
+    "windows",
+];
+
+/// A platform the project is built for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// Target triple (e.g. `x86_64-pc-windows-msvc`), or `host`
+    pub name: String,
+
+    /// Architecture, as in `target_arch`
+    pub arch: String,
+
+    /// Operating system, as in `target_os`
+    pub os: String,
+}
+
+impl Target {
+    /// The platform cargo-audit itself is running on
+    pub fn host() -> Self {
+        Target {
+            name: "host".to_owned(),
+            arch: consts::ARCH.to_owned(),
+            os: consts::OS.to_owned(),
+        }
+    }
+
+    /// Parse a target triple such as `x86_64-unknown-linux-gnu`
+    pub fn parse(triple: &str) -> Result<Self, String> {
+        let parts: Vec<_> = triple.split('-').collect();
+
+        if parts.len() < 2 || parts.iter().any(|part| part.is_empty()) {
+            return Err(format!("invalid target triple `{}`", triple));
+        }
+
+        let arch = match parts[0] {
+            "i386" | "i586" | "i686" => "x86",
+            "mipsel" => "mips",
+            "mips64el" => "mips64",
+            "powerpc64le" => "powerpc64",
+            "sparcv9" => "sparc64",
+            arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm",
+            arch => arch,
+        };
+
+        // The OS is usually the third component, but the vendor is sometimes omitted (as in
+        // `x86_64-linux-android`), and some environments imply a different OS
+        let os = if parts.contains(&"android") || parts.contains(&"androideabi") {
+            "android"
+        } else if parts.contains(&"darwin") {
+            "macos"
+        } else {
+            parts[1..]
+                .iter()
+                .cloned()
+                .find(|part| OSES.contains(part))
+                .unwrap_or("unknown")
+        };
+
+        Ok(Target {
+            name: triple.to_owned(),
+            arch: arch.to_owned(),
+            os: os.to_owned(),
+        })
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.name == "host" {
+            write!(fmt, "host ({}-{})", self.arch, self.os)
+        } else {
+            write!(fmt, "{}", self.name)
+        }
+    }
+}
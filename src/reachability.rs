@@ -0,0 +1,312 @@
+//! Checks for whether a project's own sources refer to the functions an advisory affects
+//!
+//! This is a textual scan rather than a real analysis: a function counts as referenced if its
+//! name appears as an identifier (outside comments and string literals) in a source file which
+//! also names the function's crate, e.g. in a `use` declaration or a qualified path. Vendored
+//! sources are skipped.
+//!
+//! Any file that uses the crate for anything can still produce a false positive when it also
+//! happens to use an unrelated function or method of the same name. For common names such as
+//! `new`, `parse` or `from_str` that is close to every file which imports the crate at all, so
+//! treat a reference as a prompt to look rather than proof. Conversely, calls made from files
+//! which only reach the crate through a re-export or a renamed dependency are missed.
+
+use advisory::Advisory;
+use error::Result;
+use files;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Whether any of an advisory's affected functions are used by the project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reachability {
+    /// An affected function is referenced at the given location
+    Referenced(Reference),
+
+    /// None of the affected functions are referenced
+    NotReferenced,
+}
+
+/// Location of the first reference to an affected function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Path of the affected function, as given in the advisory
+    pub function: String,
+
+    /// Source file containing the reference
+    pub file: PathBuf,
+
+    /// Line number (starting from 1) of the reference
+    pub line: usize,
+}
+
+/// Identifiers used in a project's Rust sources
+#[derive(Debug, Default)]
+pub struct SourceIndex {
+    /// Each source file, along with the first line on which each identifier appears in it
+    files: Vec<(PathBuf, HashMap<String, usize>)>,
+}
+
+impl Reachability {
+    /// Short label for the finding, e.g. `not referenced`
+    pub fn label(&self) -> &'static str {
+        match *self {
+            Reachability::Referenced(_) => "referenced",
+            Reachability::NotReferenced => "not referenced",
+        }
+    }
+}
+
+impl SourceIndex {
+    /// Index all `.rs` files beneath the given directory, skipping build output, vendored
+    /// sources and hidden directories
+    pub fn build(dir: &Path) -> Result<Self> {
+        let files = find_rust_files(if dir == Path::new("") {
+            Path::new(".")
+        } else {
+            dir
+        })?;
+
+        let mut index = SourceIndex::default();
+
+        for path in files {
+            let source = files::read_to_string(&path)?;
+            index.add_file(path.strip_prefix(".").unwrap_or(&path), &source);
+        }
+
+        Ok(index)
+    }
+
+    /// Check an advisory's affected functions against the index. Returns `None` if the
+    /// advisory doesn't list any.
+    pub fn reachability(&self, advisory: &Advisory) -> Option<Reachability> {
+        if advisory.affected_functions.is_empty() {
+            return None;
+        }
+
+        for function in &advisory.affected_functions {
+            let name = function.rsplit("::").next().unwrap_or(function);
+
+            // Paths start with the crate name, which is written with underscores in code
+            let krate = function
+                .split("::")
+                .next()
+                .unwrap_or(&advisory.package)
+                .replace('-', "_");
+
+            for &(ref file, ref identifiers) in &self.files {
+                if !identifiers.contains_key(&krate) {
+                    continue;
+                }
+
+                if let Some(&line) = identifiers.get(name) {
+                    return Some(Reachability::Referenced(Reference {
+                        function: function.clone(),
+                        file: file.clone(),
+                        line: line,
+                    }));
+                }
+            }
+        }
+
+        Some(Reachability::NotReferenced)
+    }
+
+    /// Record the identifiers in a source file, skipping comments and literals
+    fn add_file(&mut self, path: &Path, source: &str) {
+        let mut identifiers = HashMap::new();
+        let chars: Vec<char> = source.chars().collect();
+        let mut line = 1;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).cloned();
+
+            if c == '\n' {
+                line += 1;
+                i += 1;
+            } else if c == '/' && next == Some('/') {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            } else if c == '/' && next == Some('*') {
+                // Block comments nest
+                let mut depth = 0;
+
+                while i < chars.len() {
+                    match (chars[i], chars.get(i + 1).cloned()) {
+                        ('/', Some('*')) => {
+                            depth += 1;
+                            i += 2;
+                        }
+                        ('*', Some('/')) => {
+                            depth -= 1;
+                            i += 2;
+
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        (c, _) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            i += 1;
+                        }
+                    }
+                }
+            } else if c == '"' {
+                i = skip_string(&chars, i + 1, None, &mut line);
+            } else if c == '\'' {
+                // Character literal, or otherwise a lifetime
+                i = match (next, chars.get(i + 2).cloned()) {
+                    (Some('\\'), _) => {
+                        let end = chars.iter().skip(i + 3).position(|&c| c == '\'');
+                        end.map(|end| i + end + 4).unwrap_or(chars.len())
+                    }
+                    (Some(_), Some('\'')) => i + 3,
+                    _ => i + 1,
+                };
+            } else if c.is_alphabetic() || c == '_' {
+                let start = i;
+
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+
+                let identifier: String = chars[start..i].iter().collect();
+
+                // Raw strings: `r"..."`, `r#"..."#`, `br"..."`
+                if identifier == "r" || identifier == "br" {
+                    let hashes = chars[i..].iter().take_while(|&&c| c == '#').count();
+
+                    if chars.get(i + hashes) == Some(&'"') {
+                        i = skip_string(&chars, i + hashes + 1, Some(hashes), &mut line);
+                        continue;
+                    }
+                }
+
+                identifiers.entry(identifier).or_insert(line);
+            } else {
+                i += 1;
+            }
+        }
+
+        self.files.push((path.to_owned(), identifiers));
+    }
+}
+
+/// Skip past the end of a string literal whose contents start at `i`, returning the position
+/// after it. Raw strings have no escapes, and end with `"` followed by the given number of `#`s.
+fn skip_string(chars: &[char], mut i: usize, raw: Option<usize>, line: &mut usize) -> usize {
+    let hashes = raw.unwrap_or(0);
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if raw.is_none() => i += 2,
+            '"' if chars.iter().skip(i + 1).take_while(|&&c| c == '#').count() >= hashes => {
+                return i + 1 + hashes;
+            }
+            c => {
+                if c == '\n' {
+                    *line += 1;
+                }
+                i += 1;
+            }
+        }
+    }
+
+    i
+}
+
+/// Find all Rust source files beneath a directory, in a deterministic order. Dependencies
+/// vendored into the project (e.g. by `cargo vendor`) aren't its own code, so are skipped.
+fn find_rust_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    files::find_files(dir, |path, is_dir| {
+        let skipped = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with('.') || name == "target" || name == "vendor")
+            .unwrap_or(false);
+
+        !skipped && (is_dir || path.extension().map(|ext| ext == "rs").unwrap_or(false))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reachability, Reference, SourceIndex};
+    use advisory::Advisory;
+    use std::path::{Path, PathBuf};
+    use toml;
+
+    fn advisory(functions: &str) -> Advisory {
+        let table = format!(
+            "id = \"RUSTSEC-2017-0001\"\npackage = \"smallvec\"\npatched_versions = []\n\
+             title = \"t\"\ndescription = \"d\"\naffected_functions = [{}]\n",
+            functions
+        )
+        .parse::<toml::Value>()
+        .unwrap();
+
+        Advisory::from_toml_table(table.as_table().unwrap()).unwrap()
+    }
+
+    fn index(files: &[(&str, &str)]) -> SourceIndex {
+        let mut index = SourceIndex::default();
+
+        for &(path, source) in files {
+            index.add_file(Path::new(path), source);
+        }
+
+        index
+    }
+
+    #[test]
+    fn requires_crate_in_same_file() {
+        let advisory = advisory("\"smallvec::SmallVec::insert_many\"");
+        let index = index(&[
+            ("src/a.rs", "fn f(v: &mut Vec<u8>) {\n    v.insert_many(0, x);\n}\n"),
+            (
+                "src/b.rs",
+                "use smallvec::SmallVec;\n\nfn g(v: &mut SmallVec) {\n    v.insert_many(0, x);\n}\n",
+            ),
+        ]);
+
+        assert_eq!(
+            index.reachability(&advisory),
+            Some(Reachability::Referenced(Reference {
+                function: "smallvec::SmallVec::insert_many".to_owned(),
+                file: PathBuf::from("src/b.rs"),
+                line: 4,
+            }))
+        );
+    }
+
+    #[test]
+    fn ignores_comments_strings_and_other_crates() {
+        let advisory = advisory("\"smallvec::SmallVec::insert_many\"");
+        let index = index(&[
+            (
+                "src/a.rs",
+                "// smallvec::SmallVec::insert_many\nfn f() {}\n",
+            ),
+            (
+                "src/b.rs",
+                "fn g() { h(\"smallvec insert_many\", r#\"insert_many\"#) }\n",
+            ),
+            ("src/c.rs", "use other::insert_many;\n"),
+        ]);
+
+        assert_eq!(
+            index.reachability(&advisory),
+            Some(Reachability::NotReferenced)
+        );
+    }
+
+    #[test]
+    fn no_affected_functions() {
+        assert_eq!(SourceIndex::default().reachability(&advisory("")), None);
+    }
+}
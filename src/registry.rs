@@ -0,0 +1,211 @@
+//! Lookups in cargo's local copies of registry indexes, to find crate versions which have been
+//! yanked
+//!
+//! cargo keeps a copy of each registry index it has used under `$CARGO_HOME/registry/index`:
+//! a git repository (with or without a checked out working tree), or for sparse registries
+//! just the entries it has downloaded, under `.cache`. These are read as they are, without
+//! being updated, so they're only as fresh as the last time cargo used the registry.
+
+use config;
+use files;
+use git::Repository;
+use hyper::Url;
+use semver::Version;
+use serde_json;
+use std::fs;
+use std::path::PathBuf;
+
+/// Host names of the git and sparse crates.io indexes, as used in cargo's directory names
+const CRATES_IO_HOSTS: &'static [&'static str] = &["github.com", "index.crates.io"];
+
+/// Git revisions to try, in order, when reading from an index without a working tree
+const INDEX_REVS: &'static [&'static str] = &["origin/HEAD", "origin/master", "FETCH_HEAD", "HEAD"];
+
+/// A local copy of a registry index
+#[derive(Debug)]
+pub struct RegistryIndex {
+    path: PathBuf,
+}
+
+impl RegistryIndex {
+    /// Use the index copy in the given directory
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        RegistryIndex { path: path.into() }
+    }
+
+    /// Find cargo's copies of the index for a lockfile package source, e.g.
+    /// `registry+https://github.com/rust-lang/crates.io-index`
+    pub fn find_local(source: &str) -> Vec<Self> {
+        let url = source.splitn(2, '+').nth(1).unwrap_or(source);
+
+        let hosts: Vec<String> = if is_crates_io(source) {
+            CRATES_IO_HOSTS
+                .iter()
+                .map(|host| host.to_string())
+                .collect()
+        } else {
+            Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(|host| vec![host.to_owned()]))
+                .unwrap_or_default()
+        };
+
+        let dir = match config::cargo_home() {
+            Some(cargo_home) => cargo_home.join("registry").join("index"),
+            None => return vec![],
+        };
+
+        let mut paths: Vec<_> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect(),
+            Err(_) => return vec![],
+        };
+
+        paths.sort();
+
+        // Directories are named after the host, followed by a hash of the full URL
+        paths
+            .into_iter()
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .and_then(|name| name.rsplitn(2, '-').nth(1))
+                    .map(|host| hosts.iter().any(|h| h == host))
+                    .unwrap_or(false)
+            })
+            .map(RegistryIndex::new)
+            .collect()
+    }
+
+    /// Has the given version of a crate been yanked? Returns `None` if the index doesn't
+    /// contain the version.
+    pub fn is_yanked(&self, name: &str, version: &Version) -> Option<bool> {
+        for entry in self.entries(name)?.lines() {
+            let entry: serde_json::Value = match serde_json::from_str(entry) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+
+            let matches = entry
+                .get("vers")
+                .and_then(|vers| vers.as_str())
+                .and_then(|vers| Version::parse(vers).ok())
+                .map(|vers| vers == *version)
+                .unwrap_or(false);
+
+            if matches {
+                return Some(
+                    entry
+                        .get("yanked")
+                        .and_then(|y| y.as_bool())
+                        .unwrap_or(false),
+                );
+            }
+        }
+
+        None
+    }
+
+    /// The index entries for a crate, one JSON object per line
+    fn entries(&self, name: &str) -> Option<String> {
+        let path = entry_path(name);
+
+        // Entries cached by cargo are stored after a small header, as NUL-separated pairs of
+        // version numbers and JSON objects
+        if let Ok(data) = files::read(&self.path.join(".cache").join(&path)) {
+            let entries: Vec<_> = data
+                .split(|&b| b == 0)
+                .filter(|part| part.first() == Some(&b'{'))
+                .map(|part| String::from_utf8_lossy(part).into_owned())
+                .collect();
+
+            return Some(entries.join("\n"));
+        }
+
+        if let Ok(data) = files::read(&self.path.join(&path)) {
+            return Some(String::from_utf8_lossy(&data).into_owned());
+        }
+
+        if self.path.join(".git").exists() {
+            let repo = Repository::new(&self.path);
+
+            return INDEX_REVS
+                .iter()
+                .filter_map(|rev| repo.read_file(rev, &path).ok())
+                .next();
+        }
+
+        None
+    }
+}
+
+/// Is a lockfile package source the crates.io registry (through either its git or sparse
+/// index)?
+pub fn is_crates_io(source: &str) -> bool {
+    source == "registry+https://github.com/rust-lang/crates.io-index"
+        || source == "sparse+https://index.crates.io/"
+}
+
+/// Location of a crate's entries within the index, e.g. `se/rd/serde`
+fn entry_path(name: &str) -> String {
+    let name = name.to_lowercase();
+
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entry_path, is_crates_io, RegistryIndex};
+    use semver::Version;
+    use std::path::Path;
+
+    fn index() -> RegistryIndex {
+        RegistryIndex::new(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/support/index"))
+    }
+
+    fn is_yanked(name: &str, version: &str) -> Option<bool> {
+        index().is_yanked(name, &Version::parse(version).unwrap())
+    }
+
+    #[test]
+    fn entry_paths() {
+        assert_eq!(entry_path("a"), "1/a");
+        assert_eq!(entry_path("ab"), "2/ab");
+        assert_eq!(entry_path("abc"), "3/a/abc");
+        assert_eq!(entry_path("abcd"), "ab/cd/abcd");
+        assert_eq!(entry_path("Serde_JSON"), "se/rd/serde_json");
+    }
+
+    #[test]
+    fn yanked_from_index_file() {
+        assert_eq!(is_yanked("heffalump", "1.0.0"), Some(false));
+        assert_eq!(is_yanked("heffalump", "1.0.1"), Some(true));
+        assert_eq!(is_yanked("heffalump", "2.0.0"), None);
+        assert_eq!(is_yanked("woozles", "1.0.0"), None);
+    }
+
+    #[test]
+    fn yanked_from_cache_file() {
+        assert_eq!(is_yanked("woozle", "0.1.0"), Some(false));
+        assert_eq!(is_yanked("woozle", "0.2.0"), Some(true));
+        assert_eq!(is_yanked("woozle", "0.3.0"), None);
+    }
+
+    #[test]
+    fn crates_io_sources() {
+        assert!(is_crates_io(
+            "registry+https://github.com/rust-lang/crates.io-index"
+        ));
+        assert!(is_crates_io("sparse+https://index.crates.io/"));
+        assert!(!is_crates_io("registry+https://example.com/index"));
+        assert!(!is_crates_io(
+            "git+https://github.com/rust-lang/crates.io-index"
+        ));
+    }
+}
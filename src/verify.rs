@@ -0,0 +1,144 @@
+//! Integrity verification of advisory database contents
+//!
+//! Databases can be checked against pinned SHA-256 digests and/or a detached OpenPGP signature.
+//! Signatures are verified by `gpgv` against an explicitly given keyring, so the user's own
+//! GnuPG trust database plays no part in deciding which keys are acceptable.
+
+use cache::Cache;
+use error::{Error, Result};
+use files;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Integrity checks to perform on advisory databases
+#[derive(Debug, Default)]
+pub struct Integrity<'a> {
+    /// Acceptable SHA-256 digests (hex-encoded) of the database
+    pub sha256: Vec<&'a str>,
+
+    /// Keyring containing the public keys trusted to sign the database
+    pub keyring: Option<&'a Path>,
+
+    /// Location of the detached signature, if not alongside the database with an `.asc` suffix
+    pub signature: Option<&'a str>,
+}
+
+impl<'a> Integrity<'a> {
+    /// Are any integrity checks enabled?
+    pub fn is_enabled(&self) -> bool {
+        !self.sha256.is_empty() || self.keyring.is_some()
+    }
+
+    /// Obtain the detached signature for the database at the given location (a URL or local
+    /// path), or `None` if signatures aren't being checked. Signatures at URLs are obtained
+    /// with `fetch`.
+    pub fn fetch_signature<F>(&self, location: &str, fetch: F) -> Result<Option<Vec<u8>>>
+    where
+        F: FnOnce(&str) -> Result<Vec<u8>>,
+    {
+        if self.keyring.is_none() {
+            return Ok(None);
+        }
+
+        let location = match self.signature {
+            Some(signature) => signature.to_owned(),
+            None => format!("{}.asc", location),
+        };
+
+        if location.starts_with("http://") || location.starts_with("https://") {
+            return fetch(&location).map(Some);
+        }
+
+        files::read(Path::new(&location)).map(Some)
+    }
+
+    /// Check the database contents against the pinned digests and/or signature
+    pub fn check(&self, data: &[u8], signature: Option<&[u8]>) -> Result<()> {
+        if !self.sha256.is_empty() {
+            check_sha256(data, &self.sha256)?;
+        }
+
+        if let Some(keyring) = self.keyring {
+            match signature {
+                Some(signature) => check_signature(data, signature, keyring)?,
+                None => return Err(Error::Verification("no signature found".to_owned())),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that the SHA-256 digest of `data` matches one of the pinned digests
+fn check_sha256(data: &[u8], pinned: &[&str]) -> Result<()> {
+    let digest: String = Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    if pinned
+        .iter()
+        .any(|expected| expected.trim().eq_ignore_ascii_case(&digest))
+    {
+        Ok(())
+    } else {
+        Err(Error::Verification(format!(
+            "SHA-256 digest {} does not match any pinned digest",
+            digest
+        )))
+    }
+}
+
+/// Check a detached OpenPGP `signature` over `data` using the public keys in `keyring`
+fn check_signature(data: &[u8], signature: &[u8], keyring: &Path) -> Result<()> {
+    // gpgv looks up keyrings without a path separator in the GnuPG home directory
+    let keyring = fs::canonicalize(keyring)
+        .map_err(|e| Error::IO(format!("{}: {}", keyring.display(), e)))?;
+
+    // gpgv reads the signature from a file, but the signed data can be streamed over stdin
+    let sig_path = write_signature(signature)?;
+    let result = run_gpgv(data, &sig_path, &keyring);
+    let _ = fs::remove_file(&sig_path);
+    result
+}
+
+/// Write a signature to a new file for gpgv to read, in the user's own cache directory rather
+/// than the shared temporary directory where possible
+fn write_signature(signature: &[u8]) -> Result<PathBuf> {
+    let dir = Cache::default_dir().unwrap_or_else(env::temp_dir);
+    fs::create_dir_all(&dir).map_err(|e| Error::IO(format!("{}: {}", dir.display(), e)))?;
+    files::write_new(&dir, "sig", signature)
+}
+
+fn run_gpgv(data: &[u8], sig_path: &Path, keyring: &Path) -> Result<()> {
+    let mut child = Command::new("gpgv")
+        .arg("--keyring")
+        .arg(keyring)
+        .arg(sig_path)
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::IO(format!("couldn't run gpgv: {}", e)))?;
+
+    // gpgv may exit early (e.g. on a malformed signature) without consuming all of stdin
+    let _ = child.stdin.take().unwrap().write_all(data);
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| Error::IO(format!("couldn't run gpgv: {}", e)))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Verification(format!(
+            "bad signature: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
@@ -0,0 +1,446 @@
+//! Version requirements as used in advisories, and the ranges of versions they describe
+//!
+//! Requirements are evaluated purely by semver precedence. Unlike cargo's dependency
+//! resolution, a pre-release such as `2.0.0-alpha.1` *does* match `>= 1.1.0`, since a
+//! pre-release of a later version contains the fix as well.
+
+use semver::{Identifier, Version};
+use semver_parser::range::{self, Op, Predicate, WildcardVersion};
+use semver_parser::version::Identifier as ParsedIdentifier;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A version requirement such as `>= 1.2.0, < 1.3.0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    /// The requirement as written
+    text: String,
+
+    /// Versions matching the requirement
+    range: Range,
+}
+
+/// A contiguous range of versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    lower: Bound,
+    upper: Bound,
+}
+
+/// One end of a range of versions
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Bound {
+    Unbounded,
+    Inclusive(Version),
+    Exclusive(Version),
+}
+
+impl VersionReq {
+    /// Parse a comma-separated list of predicates, all of which must match
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let parsed = range::parse(text)?;
+        let mut range = Range::all();
+
+        for predicate in &parsed.predicates {
+            range = range.intersect(&predicate_range(predicate));
+        }
+
+        Ok(VersionReq {
+            text: text.trim().to_owned(),
+            range: range,
+        })
+    }
+
+    /// Does the given version satisfy this requirement?
+    pub fn matches(&self, version: &Version) -> bool {
+        self.range.contains(version)
+    }
+
+    /// Range of versions satisfying this requirement
+    pub fn range(&self) -> &Range {
+        &self.range
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.text)
+    }
+}
+
+impl Range {
+    /// Range containing every version
+    pub fn all() -> Self {
+        Range {
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+        }
+    }
+
+    /// Is the given version within this range?
+    pub fn contains(&self, version: &Version) -> bool {
+        let above = match self.lower {
+            Bound::Unbounded => true,
+            Bound::Inclusive(ref lower) => version >= lower,
+            Bound::Exclusive(ref lower) => version > lower,
+        };
+
+        let below = match self.upper {
+            Bound::Unbounded => true,
+            Bound::Inclusive(ref upper) => version <= upper,
+            Bound::Exclusive(ref upper) => version < upper,
+        };
+
+        above && below
+    }
+
+    /// Does this range contain no versions at all?
+    pub fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (&Bound::Inclusive(ref lower), &Bound::Inclusive(ref upper)) => lower > upper,
+            (&Bound::Inclusive(ref lower), &Bound::Exclusive(ref upper))
+            | (&Bound::Exclusive(ref lower), &Bound::Inclusive(ref upper))
+            | (&Bound::Exclusive(ref lower), &Bound::Exclusive(ref upper)) => lower >= upper,
+            _ => false,
+        }
+    }
+
+    /// Versions contained in both this range and `other`
+    pub fn intersect(&self, other: &Range) -> Range {
+        let lower = match cmp_lower(&self.lower, &other.lower) {
+            Ordering::Less => other.lower.clone(),
+            _ => self.lower.clone(),
+        };
+
+        let upper = match cmp_upper(&self.upper, &other.upper) {
+            Ordering::Greater => other.upper.clone(),
+            _ => self.upper.clone(),
+        };
+
+        Range {
+            lower: lower,
+            upper: upper,
+        }
+    }
+
+    /// Versions which aren't contained in any of the given ranges, as a sorted list of
+    /// disjoint ranges
+    pub fn complement(ranges: &[&Range]) -> Vec<Range> {
+        let mut ranges: Vec<_> = ranges.iter().filter(|range| !range.is_empty()).collect();
+        ranges.sort_by(|a, b| cmp_lower(&a.lower, &b.lower));
+
+        let mut result = vec![];
+
+        // Upper bound of the versions covered by the ranges seen so far
+        let mut covered: Option<Bound> = None;
+
+        for range in ranges {
+            let gap = Range {
+                lower: covered.as_ref().map(flip).unwrap_or(Bound::Unbounded),
+                upper: flip(&range.lower),
+            };
+
+            if range.lower != Bound::Unbounded && !gap.is_empty() {
+                result.push(gap);
+            }
+
+            covered = match covered {
+                Some(ref end) if cmp_upper(end, &range.upper) == Ordering::Greater => {
+                    Some(end.clone())
+                }
+                _ => Some(range.upper.clone()),
+            };
+
+            if covered == Some(Bound::Unbounded) {
+                return result;
+            }
+        }
+
+        result.push(Range {
+            lower: covered.as_ref().map(flip).unwrap_or(Bound::Unbounded),
+            upper: Bound::Unbounded,
+        });
+
+        result
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.lower, &self.upper) {
+            (&Bound::Unbounded, &Bound::Unbounded) => write!(fmt, "*"),
+            (&Bound::Inclusive(ref lower), &Bound::Inclusive(ref upper)) if lower == upper => {
+                write!(fmt, "= {}", lower)
+            }
+            (lower, upper) => {
+                let lower = match *lower {
+                    Bound::Unbounded => None,
+                    Bound::Inclusive(ref v) => Some(format!(">= {}", display_version(v))),
+                    Bound::Exclusive(ref v) => Some(format!("> {}", display_version(v))),
+                };
+
+                let upper = match *upper {
+                    Bound::Unbounded => None,
+                    Bound::Inclusive(ref v) => Some(format!("<= {}", display_version(v))),
+                    Bound::Exclusive(ref v) => Some(format!("< {}", display_version(v))),
+                };
+
+                let parts: Vec<_> = lower.into_iter().chain(upper).collect();
+                write!(fmt, "{}", parts.join(", "))
+            }
+        }
+    }
+}
+
+/// Order lower bounds by the smallest version they admit
+fn cmp_lower(a: &Bound, b: &Bound) -> Ordering {
+    match (a, b) {
+        (&Bound::Unbounded, &Bound::Unbounded) => Ordering::Equal,
+        (&Bound::Unbounded, _) => Ordering::Less,
+        (_, &Bound::Unbounded) => Ordering::Greater,
+        (&Bound::Inclusive(ref a), &Bound::Exclusive(ref b)) if a == b => Ordering::Less,
+        (&Bound::Exclusive(ref a), &Bound::Inclusive(ref b)) if a == b => Ordering::Greater,
+        (&Bound::Inclusive(ref a), &Bound::Inclusive(ref b))
+        | (&Bound::Inclusive(ref a), &Bound::Exclusive(ref b))
+        | (&Bound::Exclusive(ref a), &Bound::Inclusive(ref b))
+        | (&Bound::Exclusive(ref a), &Bound::Exclusive(ref b)) => a.cmp(b),
+    }
+}
+
+/// Order upper bounds by the largest version they admit
+fn cmp_upper(a: &Bound, b: &Bound) -> Ordering {
+    match (a, b) {
+        (&Bound::Unbounded, &Bound::Unbounded) => Ordering::Equal,
+        (&Bound::Unbounded, _) => Ordering::Greater,
+        (_, &Bound::Unbounded) => Ordering::Less,
+        (&Bound::Exclusive(ref a), &Bound::Inclusive(ref b)) if a == b => Ordering::Less,
+        (&Bound::Inclusive(ref a), &Bound::Exclusive(ref b)) if a == b => Ordering::Greater,
+        (&Bound::Inclusive(ref a), &Bound::Inclusive(ref b))
+        | (&Bound::Inclusive(ref a), &Bound::Exclusive(ref b))
+        | (&Bound::Exclusive(ref a), &Bound::Inclusive(ref b))
+        | (&Bound::Exclusive(ref a), &Bound::Exclusive(ref b)) => a.cmp(b),
+    }
+}
+
+/// The bound on the other side of the same version, e.g. `< 1.0.0` for `>= 1.0.0`
+fn flip(bound: &Bound) -> Bound {
+    match *bound {
+        Bound::Unbounded => Bound::Unbounded,
+        Bound::Inclusive(ref v) => Bound::Exclusive(v.clone()),
+        Bound::Exclusive(ref v) => Bound::Inclusive(v.clone()),
+    }
+}
+
+/// Range of versions matching a single predicate, using the same interpretation of partial
+/// versions as cargo (e.g. `~1.2` means `>= 1.2.0, < 1.3.0`)
+fn predicate_range(predicate: &Predicate) -> Range {
+    let major = predicate.major;
+    let pre: Vec<_> = predicate.pre.iter().map(identifier).collect();
+    let exact = version(
+        major,
+        predicate.minor.unwrap_or(0),
+        predicate.patch.unwrap_or(0),
+        pre,
+    );
+
+    // Exclusive upper bounds stop before any pre-releases of the next version
+    let before = |major, minor, patch| Bound::Exclusive(version(major, minor, patch, first_pre()));
+    let next_major = || before(major + 1, 0, 0);
+    let next_minor = |minor| before(major, minor + 1, 0);
+
+    let (lower, upper) = match predicate.op {
+        Op::Ex => match (predicate.minor, predicate.patch) {
+            (Some(_), Some(_)) => (Bound::Inclusive(exact.clone()), Bound::Inclusive(exact)),
+            (Some(minor), None) => (Bound::Inclusive(exact), next_minor(minor)),
+            (None, _) => (Bound::Inclusive(exact), next_major()),
+        },
+        Op::Gt => match (predicate.minor, predicate.patch) {
+            (Some(_), Some(_)) => (Bound::Exclusive(exact), Bound::Unbounded),
+            (Some(minor), None) => (flip(&next_minor(minor)), Bound::Unbounded),
+            (None, _) => (flip(&next_major()), Bound::Unbounded),
+        },
+        Op::GtEq => (Bound::Inclusive(exact), Bound::Unbounded),
+        Op::Lt => (Bound::Unbounded, Bound::Exclusive(exact)),
+        Op::LtEq => match (predicate.minor, predicate.patch) {
+            (Some(_), Some(_)) => (Bound::Unbounded, Bound::Inclusive(exact)),
+            (Some(minor), None) => (Bound::Unbounded, next_minor(minor)),
+            (None, _) => (Bound::Unbounded, next_major()),
+        },
+        Op::Tilde => match predicate.minor {
+            Some(minor) => (Bound::Inclusive(exact), next_minor(minor)),
+            None => (Bound::Inclusive(exact), next_major()),
+        },
+        Op::Compatible => match (major, predicate.minor, predicate.patch) {
+            (0, Some(0), Some(patch)) => (Bound::Inclusive(exact), before(0, 0, patch + 1)),
+            (0, Some(minor), _) => (Bound::Inclusive(exact), next_minor(minor)),
+            _ => (Bound::Inclusive(exact), next_major()),
+        },
+        Op::Wildcard(WildcardVersion::Major) => (Bound::Unbounded, Bound::Unbounded),
+        Op::Wildcard(WildcardVersion::Minor) => (Bound::Inclusive(exact), next_major()),
+        Op::Wildcard(WildcardVersion::Patch) => {
+            let minor = predicate.minor.unwrap_or(0);
+            (Bound::Inclusive(exact), next_minor(minor))
+        }
+    };
+
+    Range {
+        lower: lower,
+        upper: upper,
+    }
+}
+
+fn version(major: u64, minor: u64, patch: u64, pre: Vec<Identifier>) -> Version {
+    Version {
+        major: major,
+        minor: minor,
+        patch: patch,
+        pre: pre,
+        build: vec![],
+    }
+}
+
+/// The lowest possible pre-release identifier (as in `1.0.0-0`)
+fn first_pre() -> Vec<Identifier> {
+    vec![Identifier::Numeric(0)]
+}
+
+/// A version as shown to users. The `-0` pre-release used internally to place bounds before
+/// any pre-releases of a version is left out, so `< 1.3.0-0` reads as `< 1.3.0`.
+fn display_version(version: &Version) -> String {
+    if version.pre == first_pre() {
+        format!("{}.{}.{}", version.major, version.minor, version.patch)
+    } else {
+        version.to_string()
+    }
+}
+
+fn identifier(identifier: &ParsedIdentifier) -> Identifier {
+    match *identifier {
+        ParsedIdentifier::Numeric(n) => Identifier::Numeric(n),
+        ParsedIdentifier::AlphaNumeric(ref s) => Identifier::AlphaNumeric(s.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Range, VersionReq};
+    use semver::Version;
+
+    fn req(text: &str) -> VersionReq {
+        VersionReq::parse(text).unwrap()
+    }
+
+    fn matches(text: &str, version: &str) -> bool {
+        req(text).matches(&Version::parse(version).unwrap())
+    }
+
+    fn complement(reqs: &[&str]) -> Vec<String> {
+        let reqs: Vec<_> = reqs.iter().map(|text| req(text)).collect();
+        let ranges: Vec<_> = reqs.iter().map(|req| req.range()).collect();
+
+        Range::complement(&ranges)
+            .iter()
+            .map(|range| range.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn caret_zero_zero_patch() {
+        assert_eq!(req("^0.0.3").range().to_string(), ">= 0.0.3, < 0.0.4");
+        assert!(matches("^0.0.3", "0.0.3"));
+        assert!(!matches("^0.0.3", "0.0.4"));
+        assert!(!matches("^0.0.3", "0.0.4-alpha"));
+        assert!(!matches("^0.0.3", "0.0.2"));
+    }
+
+    #[test]
+    fn caret() {
+        assert_eq!(req("^0.9.5").range().to_string(), ">= 0.9.5, < 0.10.0");
+        assert_eq!(req("^1.2").range().to_string(), ">= 1.2.0, < 2.0.0");
+        assert!(matches("^1.2", "1.9.9"));
+        assert!(!matches("^1.2", "2.0.0-rc.1"));
+    }
+
+    #[test]
+    fn tilde() {
+        assert_eq!(req("~1.2.3").range().to_string(), ">= 1.2.3, < 1.3.0");
+        assert_eq!(req("~1").range().to_string(), ">= 1.0.0, < 2.0.0");
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.3.0"));
+    }
+
+    #[test]
+    fn wildcards() {
+        assert_eq!(req("*").range().to_string(), "*");
+        assert_eq!(req("1.*").range().to_string(), ">= 1.0.0, < 2.0.0");
+        assert_eq!(req("1.2.*").range().to_string(), ">= 1.2.0, < 1.3.0");
+        assert!(matches("1.2.*", "1.2.7"));
+        assert!(!matches("1.2.*", "1.3.0"));
+    }
+
+    #[test]
+    fn exact_and_partial_comparisons() {
+        assert_eq!(req("= 1.2.3").range().to_string(), "= 1.2.3");
+        assert_eq!(req("> 1.2").range().to_string(), ">= 1.3.0");
+        assert_eq!(req("<= 1.2").range().to_string(), "< 1.3.0");
+    }
+
+    #[test]
+    fn pre_releases() {
+        // A pre-release of a later version contains the fix
+        assert!(matches(">= 1.1.0", "2.0.0-alpha.1"));
+        assert!(!matches(">= 1.1.0", "1.1.0-beta"));
+        assert!(matches("< 1.1.0", "1.1.0-beta"));
+        assert!(matches(">= 1.0.0-beta.2", "1.0.0-beta.10"));
+        assert!(!matches(">= 1.0.0-beta.2", "1.0.0-beta.1"));
+        assert_eq!(
+            req(">= 1.0.0-beta.2").range().to_string(),
+            ">= 1.0.0-beta.2"
+        );
+    }
+
+    #[test]
+    fn complement_of_nothing() {
+        assert_eq!(complement(&[]), vec!["*"]);
+        assert_eq!(complement(&[">= 2.0.0, < 1.0.0"]), vec!["*"]);
+        assert_eq!(complement(&["*"]), Vec::<String>::new());
+    }
+
+    #[test]
+    fn complement_of_one_sided_ranges() {
+        assert_eq!(complement(&[">= 1.2.0"]), vec!["< 1.2.0"]);
+        assert_eq!(complement(&["< 1.0.0"]), vec![">= 1.0.0"]);
+    }
+
+    #[test]
+    fn complement_of_overlapping_ranges() {
+        assert_eq!(
+            complement(&[">= 1.5.0, < 3.0.0", ">= 1.0.0, < 2.0.0"]),
+            vec!["< 1.0.0", ">= 3.0.0"]
+        );
+        assert_eq!(
+            complement(&[">= 1.0.0, < 3.0.0", ">= 1.5.0, < 2.0.0"]),
+            vec!["< 1.0.0", ">= 3.0.0"]
+        );
+    }
+
+    #[test]
+    fn complement_of_adjacent_ranges() {
+        assert_eq!(
+            complement(&[">= 1.0.0, < 2.0.0", ">= 2.0.0"]),
+            vec!["< 1.0.0"]
+        );
+        assert_eq!(
+            complement(&["^0.9.5", ">= 1.2.0"]),
+            vec!["< 0.9.5", ">= 0.10.0, < 1.2.0"]
+        );
+    }
+
+    #[test]
+    fn complement_of_disjoint_ranges() {
+        assert_eq!(
+            complement(&["= 1.0.0", "> 1.0.0, <= 1.5.0", ">= 2.0.0"]),
+            vec!["< 1.0.0", "> 1.5.0, < 2.0.0"]
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Locating a project's lockfile, using the same rules as cargo
+//!
+//! cargo starts from the nearest `Cargo.toml` in the current directory or its ancestors (or
+//! the one given by `--manifest-path`), and puts the lockfile at the root of the workspace
+//! that manifest belongs to. Projects without a lockfile can have one generated by cargo.
+//!
+//! Repositories containing several projects can instead be searched for all their lockfiles.
+
+use error::{Error, Result};
+use files;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml;
+
+/// Name of cargo's manifest file
+const MANIFEST: &'static str = "Cargo.toml";
+
+/// Name of cargo's lockfile
+const LOCKFILE: &'static str = "Cargo.lock";
+
+/// Find the lockfile for the given manifest, or else for the nearest manifest to the current
+/// directory. Returns `None` if the current directory isn't within a cargo project.
+pub fn find_lockfile(manifest: Option<&Path>) -> Result<Option<PathBuf>> {
+    let cwd = env::current_dir().map_err(|e| Error::IO(format!("current directory: {}", e)))?;
+
+    let manifest = match manifest {
+        Some(manifest) => cwd.join(manifest),
+        None => match cwd
+            .ancestors()
+            .map(|dir| dir.join(MANIFEST))
+            .find(|path| path.is_file())
+        {
+            Some(manifest) => manifest,
+            None => return Ok(None),
+        },
+    };
+
+    let root = workspace_root(&manifest)?;
+    let lockfile = root.join(LOCKFILE);
+
+    // Show paths within the current directory relative to it, as the user would write them
+    Ok(Some(match lockfile.strip_prefix(&cwd) {
+        Ok(relative) => relative.to_owned(),
+        Err(_) => lockfile.clone(),
+    }))
+}
+
+/// Root directory of the workspace a package belongs to: the directory named by its
+/// `package.workspace` key, or else the nearest directory (starting with the package's own)
+/// whose manifest has a `[workspace]` table not excluding the package
+fn workspace_root(manifest: &Path) -> Result<PathBuf> {
+    let package_dir = manifest.parent().unwrap_or_else(|| Path::new(""));
+    let value = parse_manifest(manifest)?;
+
+    let explicit_root = value
+        .get("package")
+        .and_then(|package| package.get("workspace"))
+        .and_then(|workspace| workspace.as_str());
+
+    if let Some(root) = explicit_root {
+        return Ok(package_dir.join(root));
+    }
+
+    if value.get("workspace").is_some() {
+        return Ok(package_dir.to_owned());
+    }
+
+    for dir in package_dir.ancestors().skip(1) {
+        let manifest = dir.join(MANIFEST);
+
+        if !manifest.is_file() {
+            continue;
+        }
+
+        let value = parse_manifest(&manifest)?;
+        let workspace = match value.get("workspace") {
+            Some(workspace) => workspace,
+            None => continue,
+        };
+
+        let excluded = workspace
+            .get("exclude")
+            .and_then(|exclude| exclude.as_array())
+            .map(|exclude| {
+                exclude
+                    .iter()
+                    .filter_map(|path| path.as_str())
+                    .any(|path| package_dir.starts_with(dir.join(path)))
+            })
+            .unwrap_or(false);
+
+        if !excluded {
+            return Ok(dir.to_owned());
+        }
+    }
+
+    Ok(package_dir.to_owned())
+}
+
+fn parse_manifest(path: &Path) -> Result<toml::Value> {
+    files::read_to_string(path)?
+        .parse()
+        .map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))
+}
+
+/// Run `cargo generate-lockfile` for the project in the given directory, or for the given
+/// manifest. The cargo binary can be overridden with `$CARGO`, as cargo does for subcommands.
+pub fn generate_lockfile(dir: &Path, manifest: Option<&Path>) -> Result<()> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut command = Command::new(&cargo);
+    command.arg("generate-lockfile");
+
+    match manifest {
+        Some(manifest) => {
+            command.arg("--manifest-path").arg(manifest);
+        }
+        None => {
+            if dir != Path::new("") {
+                command.current_dir(dir);
+            }
+        }
+    }
+
+    let output = command.output().map_err(|e| {
+        Error::IO(format!(
+            "couldn't run {}: {}",
+            Path::new(&cargo).display(),
+            e
+        ))
+    })?;
+
+    if !output.status.success() {
+        return Err(Error::IO(format!(
+            "cargo generate-lockfile failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Find every lockfile beneath a directory, in a deterministic order. Build output (`target`)
+/// and `.git` directories are skipped, as is anything matching one of the `exclude` globs.
+pub fn find_lockfiles(dir: &Path, exclude: &[&str]) -> Result<Vec<PathBuf>> {
+    let lockfiles = files::find_files(dir, |relative, is_dir| {
+        let name = match relative.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            None => return false,
+        };
+
+        // Excludes are matched against paths relative to the directory being searched
+        let relative: Vec<_> = relative
+            .iter()
+            .map(|component| component.to_string_lossy())
+            .collect();
+
+        if exclude
+            .iter()
+            .any(|pattern| glob_matches(pattern, &relative.join("/")))
+        {
+            return false;
+        }
+
+        if is_dir {
+            name != "target" && name != ".git"
+        } else {
+            name == LOCKFILE
+        }
+    })?;
+
+    Ok(lockfiles
+        .iter()
+        .map(|path| path.strip_prefix(".").unwrap_or(path).to_owned())
+        .collect())
+}
+
+/// Does a relative path match a glob? `*` and `?` match within a single path component, and
+/// `**` matches any number of components. As in `.gitignore`, patterns without a `/` can match
+/// just the last component, so `vendor` excludes every directory with that name.
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_matches('/');
+    let pattern: Vec<char> = pattern.chars().collect();
+
+    if !pattern.contains(&'/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        let name: Vec<char> = name.chars().collect();
+
+        if glob_match_chars(&pattern, &name) {
+            return true;
+        }
+    }
+
+    let path: Vec<char> = path.chars().collect();
+    glob_match_chars(&pattern, &path)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(&'*') if pattern.get(1) == Some(&'*') => {
+            // `**/` matches any number of whole components, including none
+            if pattern.get(2) == Some(&'/') {
+                let rest = &pattern[3..];
+
+                glob_match_chars(rest, text)
+                    || (0..text.len())
+                        .filter(|&i| text[i] == '/')
+                        .any(|i| glob_match_chars(rest, &text[i + 1..]))
+            } else {
+                (0..text.len() + 1).any(|i| glob_match_chars(&pattern[2..], &text[i..]))
+            }
+        }
+        Some(&'*') => (0..text.len() + 1)
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_chars(&pattern[1..], &text[i..])),
+        Some(&'?') => match text.first() {
+            Some(&c) if c != '/' => glob_match_chars(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some(&c) => text.first() == Some(&c) && glob_match_chars(&pattern[1..], &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_matches, workspace_root, MANIFEST};
+    use files::ScratchDir;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+
+    /// Write a manifest into the given directory (relative to `root`), returning its path
+    fn manifest(root: &Path, dir: &str, contents: &str) -> PathBuf {
+        let dir = root.join(dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join(MANIFEST);
+        File::create(&path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .unwrap();
+        path
+    }
+
+    const PACKAGE: &'static str = "[package]\nname = \"x\"\nversion = \"0.1.0\"\n";
+
+    #[test]
+    fn workspace_root_standalone_package() {
+        let dir = ScratchDir::new("workspace-standalone");
+        let package = manifest(&dir, "a", PACKAGE);
+
+        assert_eq!(workspace_root(&package).unwrap(), dir.join("a"));
+    }
+
+    #[test]
+    fn workspace_root_own_workspace() {
+        let dir = ScratchDir::new("workspace-own");
+        manifest(&dir, "", "[workspace]\n");
+        let package = manifest(&dir, "a", &format!("{}[workspace]\n", PACKAGE));
+
+        assert_eq!(workspace_root(&package).unwrap(), dir.join("a"));
+    }
+
+    #[test]
+    fn workspace_root_virtual_manifest() {
+        let dir = ScratchDir::new("workspace-virtual");
+        manifest(&dir, "", "[workspace]\nmembers = [\"crates/*\"]\n");
+        let package = manifest(&dir, "crates/a", PACKAGE);
+
+        assert_eq!(workspace_root(&package).unwrap(), dir.to_owned());
+    }
+
+    #[test]
+    fn workspace_root_explicit() {
+        let dir = ScratchDir::new("workspace-explicit");
+        manifest(&dir, "", "[workspace]\nmembers = [\"a\"]\n");
+        manifest(&dir, "a", "[workspace]\nmembers = [\"b\"]\n");
+        let package = manifest(
+            &dir,
+            "a/b",
+            &PACKAGE.replace("[package]\n", "[package]\nworkspace = \"../..\"\n"),
+        );
+
+        assert_eq!(workspace_root(&package).unwrap(), dir.join("a/b/../.."));
+    }
+
+    #[test]
+    fn workspace_root_excluded() {
+        let dir = ScratchDir::new("workspace-excluded");
+        manifest(&dir, "", "[workspace]\nexclude = [\"vendor\"]\n");
+        let vendored = manifest(&dir, "vendor/a", PACKAGE);
+        let member = manifest(&dir, "vendored/a", PACKAGE);
+
+        assert_eq!(workspace_root(&vendored).unwrap(), dir.join("vendor/a"));
+        assert_eq!(workspace_root(&member).unwrap(), dir.to_owned());
+    }
+
+    #[test]
+    fn glob_names_match_any_component() {
+        assert!(glob_matches("vendor", "vendor"));
+        assert!(glob_matches("vendor", "a/b/vendor"));
+        assert!(glob_matches("/vendor/", "a/vendor"));
+        assert!(!glob_matches("vendor", "vendored"));
+        assert!(!glob_matches("vendor", "vendor/a"));
+    }
+
+    #[test]
+    fn glob_double_star_suffix() {
+        assert!(glob_matches("vendor/**", "vendor/Cargo.lock"));
+        assert!(glob_matches("vendor/**", "vendor/a/b/Cargo.lock"));
+        assert!(!glob_matches("vendor/**", "a/vendor/Cargo.lock"));
+    }
+
+    #[test]
+    fn glob_double_star_prefix() {
+        assert!(glob_matches("**/fixtures", "fixtures"));
+        assert!(glob_matches("**/fixtures", "a/b/fixtures"));
+        assert!(!glob_matches("**/fixtures", "a/old-fixtures"));
+        assert!(!glob_matches("**/fixtures", "a/fixtures/b"));
+    }
+
+    #[test]
+    fn glob_star_within_component() {
+        assert!(glob_matches("a/*/Cargo.lock", "a/b/Cargo.lock"));
+        assert!(glob_matches("a/*/Cargo.lock", "a/.b/Cargo.lock"));
+        assert!(!glob_matches("a/*/Cargo.lock", "a/Cargo.lock"));
+        assert!(!glob_matches("a/*/Cargo.lock", "a/b/c/Cargo.lock"));
+        assert!(!glob_matches("a/*/Cargo.lock", "x/a/b/Cargo.lock"));
+    }
+
+    #[test]
+    fn glob_question_mark() {
+        assert!(glob_matches("crate?", "crate1"));
+        assert!(glob_matches("crate?", "a/crate2"));
+        assert!(!glob_matches("crate?", "crate"));
+        assert!(!glob_matches("crate?", "crate12"));
+        assert!(!glob_matches("a?b", "a/b"));
+    }
+}